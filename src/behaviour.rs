@@ -0,0 +1,95 @@
+/// Result of ticking a behaviour tree node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Success,
+    Failure,
+    Running,
+}
+
+/// A node in a behaviour tree over blackboard `B`, the state its nodes share. Leaves
+/// read and command the ship; composites decide which of their children get to.
+pub trait Node<B> {
+    fn tick(&mut self, blackboard: &mut B) -> Status;
+}
+
+pub type Tree<B> = Box<dyn Node<B>>;
+
+/// Ticks children in order until one doesn't fail.
+pub struct Selector<B> {
+    children: Vec<Tree<B>>,
+}
+
+impl<B> Node<B> for Selector<B> {
+    fn tick(&mut self, blackboard: &mut B) -> Status {
+        for child in &mut self.children {
+            match child.tick(blackboard) {
+                Status::Failure => continue,
+                status => return status,
+            }
+        }
+        Status::Failure
+    }
+}
+
+/// Ticks children in order until one doesn't succeed.
+pub struct Sequence<B> {
+    children: Vec<Tree<B>>,
+}
+
+impl<B> Node<B> for Sequence<B> {
+    fn tick(&mut self, blackboard: &mut B) -> Status {
+        for child in &mut self.children {
+            match child.tick(blackboard) {
+                Status::Success => continue,
+                status => return status,
+            }
+        }
+        Status::Success
+    }
+}
+
+/// Succeeds when `check` holds, without commanding anything.
+pub struct Condition<B> {
+    check: fn(&B) -> bool,
+}
+
+impl<B> Node<B> for Condition<B> {
+    fn tick(&mut self, blackboard: &mut B) -> Status {
+        if (self.check)(blackboard) {
+            Status::Success
+        } else {
+            Status::Failure
+        }
+    }
+}
+
+/// Runs its child for its side effects and reports success even when it fails, so an
+/// optional step doesn't cut a sequence short.
+pub struct Succeed<B> {
+    child: Tree<B>,
+}
+
+impl<B> Node<B> for Succeed<B> {
+    fn tick(&mut self, blackboard: &mut B) -> Status {
+        match self.child.tick(blackboard) {
+            Status::Running => Status::Running,
+            _ => Status::Success,
+        }
+    }
+}
+
+pub fn selector<B: 'static>(children: Vec<Tree<B>>) -> Tree<B> {
+    Box::new(Selector { children })
+}
+
+pub fn sequence<B: 'static>(children: Vec<Tree<B>>) -> Tree<B> {
+    Box::new(Sequence { children })
+}
+
+pub fn condition<B: 'static>(check: fn(&B) -> bool) -> Tree<B> {
+    Box::new(Condition { check })
+}
+
+pub fn succeed<B: 'static>(child: Tree<B>) -> Tree<B> {
+    Box::new(Succeed { child })
+}
@@ -0,0 +1,276 @@
+use crate::messages::{Message, RoleOrder, RosterReport};
+use crate::utils::BEACON_CHANNEL;
+use oort_api::prelude::*;
+
+/// Roster reports and orders go out on the beacon channel one tick in four, an odd tick
+/// when no ship beacons. Formation reports take the other odd ticks on their own channel.
+/// Every ship transmits in the same slot and hears it the tick after, so a collision only
+/// costs one periodic report.
+pub const COMMAND_CHANNEL: usize = BEACON_CHANNEL;
+const COMMAND_TICK: u32 = 3;
+/// A ship not heard from for this long is taken to be dead. When it was the commander,
+/// the next ship in line takes over.
+const ROSTER_TIMEOUT_TICKS: u32 = 60;
+const ORDER_TIMEOUT_TICKS: u32 = 300;
+/// Screening ships hold this far out from the ship they guard, toward the objective.
+const SCREEN_DISTANCE: f64 = 1000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// Stay with the guarded ship and take on whatever comes at it.
+    Screen,
+    /// Go after the objective.
+    Strike,
+    /// Hold station and shoot down ordnance.
+    PointDefence,
+}
+
+impl Role {
+    pub fn from_u8(value: u8) -> Role {
+        match value {
+            0 => Role::Screen,
+            1 => Role::Strike,
+            _ => Role::PointDefence,
+        }
+    }
+
+    /// What a ship of `class` does before it hears from a commander.
+    pub fn default_for(class: Class) -> Role {
+        match class {
+            Class::Fighter => Role::Strike,
+            _ => Role::PointDefence,
+        }
+    }
+}
+
+/// Fleet command worked out over the radio. Every ship checks in on `COMMAND_CHANNEL`,
+/// and the heaviest ship heard, lowest id first among equals, commands: it assigns roles
+/// from the roster and sends each ship its order in turn. Nothing is handed over when the
+/// commander dies; its reports stop, it drops off everyone's roster and the next ship in
+/// line starts giving orders.
+pub struct Commander {
+    roster: Vec<(u32, RosterReport)>,
+    order: Option<(u32, RoleOrder)>,
+    contact: Option<Vec2>,
+    next_order: usize,
+    transmissions: u32,
+    /// The role we last reported, which is also what our own roster entry carries.
+    reported_role: Role,
+}
+
+impl Default for Commander {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Commander {
+    pub fn new() -> Commander {
+        Commander {
+            roster: Vec::new(),
+            order: None,
+            contact: None,
+            next_order: 0,
+            transmissions: 0,
+            reported_role: Role::default_for(class()),
+        }
+    }
+
+    /// Takes in whatever the radio carrying command traffic heard this tick.
+    pub fn listen(&mut self, msg: Option<&[u8; 32]>) {
+        self.roster
+            .retain(|(tick, _)| current_tick() - tick < ROSTER_TIMEOUT_TICKS);
+        match msg.and_then(Message::decode) {
+            Some(Message::Roster(report)) if report.id != id() => {
+                self.roster.retain(|(_, r)| r.id != report.id);
+                self.roster.push((current_tick(), report));
+            }
+            Some(Message::RoleOrder(order)) if order.ship == id() => {
+                self.order = Some((current_tick(), order));
+            }
+            _ => {}
+        }
+    }
+
+    /// What this ship can see, passed on to the commander for picking the objective.
+    pub fn set_contact(&mut self, contact: Option<Vec2>) {
+        self.contact = contact;
+    }
+
+    /// Whether this is the tick the fleet transmits command traffic. The radio that sent
+    /// it stays on `COMMAND_CHANNEL` and hears everyone else's on the next tick.
+    pub fn is_command_tick() -> bool {
+        current_tick() % 4 == COMMAND_TICK
+    }
+
+    /// Tunes the selected radio to the command channel and sends our turn of traffic. The
+    /// commander alternates its own roster report with an order to the next ship.
+    pub fn transmit(&mut self) {
+        set_radio_channel(COMMAND_CHANNEL);
+        self.transmissions += 1;
+        self.reported_role = self.role();
+        let fleet = self.fleet();
+        if !self.is_commander() || self.transmissions.is_multiple_of(2) || fleet.len() < 2 {
+            Message::Roster(self.report()).send();
+            return;
+        }
+        let others = fleet.iter().filter(|r| r.id != id()).collect::<Vec<_>>();
+        self.next_order = (self.next_order + 1) % others.len();
+        let ship = others[self.next_order].id;
+        let order = RoleOrder {
+            commander: id(),
+            ship,
+            role: assign_role(&fleet, ship),
+            objective: objective(&fleet),
+            guard: guard(&fleet),
+        };
+        debug!("ordering {:?}", order);
+        Message::RoleOrder(order).send();
+    }
+
+    pub fn is_commander(&self) -> bool {
+        self.commander_id() == id()
+    }
+
+    pub fn commander_id(&self) -> u32 {
+        self.fleet()
+            .iter()
+            .max_by_key(|r| (class_rank(r.class), std::cmp::Reverse(r.id)))
+            .map_or(id(), |r| r.id)
+    }
+
+    pub fn role(&self) -> Role {
+        if self.is_commander() {
+            return assign_role(&self.fleet(), id());
+        }
+        self.current_order()
+            .map_or(Role::default_for(class()), |o| o.role)
+    }
+
+    /// Point the strike ships are sent to.
+    pub fn objective(&self) -> Option<Vec2> {
+        if self.is_commander() {
+            return objective(&self.fleet());
+        }
+        self.current_order().and_then(|o| o.objective)
+    }
+
+    /// Position of the ship the screen protects.
+    pub fn guard(&self) -> Option<Vec2> {
+        if self.is_commander() {
+            return guard(&self.fleet());
+        }
+        self.current_order().and_then(|o| o.guard)
+    }
+
+    /// Where our role puts us when nothing nearer calls for a move: screens between the
+    /// guarded ship and the objective, strikers `standoff` short of the objective. Point
+    /// defence holds where it is.
+    pub fn ordered_station(&self, standoff: f64) -> Option<Vec2> {
+        match self.role() {
+            Role::Screen => {
+                let guard = self.guard()?;
+                let toward = self
+                    .objective()
+                    .map_or(vec2(0.0, 0.0), |o| (o - guard).normalize());
+                Some(guard + toward * SCREEN_DISTANCE)
+            }
+            Role::Strike => {
+                let objective = self.objective()?;
+                Some(objective + (position() - objective).normalize() * standoff)
+            }
+            Role::PointDefence => None,
+        }
+    }
+
+    fn current_order(&self) -> Option<RoleOrder> {
+        self.order
+            .filter(|&(tick, order)| {
+                current_tick() - tick < ORDER_TIMEOUT_TICKS
+                    && order.commander == self.commander_id()
+            })
+            .map(|(_, order)| order)
+    }
+
+    fn report(&self) -> RosterReport {
+        RosterReport {
+            id: id(),
+            class: class(),
+            role: self.reported_role,
+            position: position(),
+            contact: self.contact,
+        }
+    }
+
+    /// Everyone on the roster and us, sorted by id.
+    fn fleet(&self) -> Vec<RosterReport> {
+        let mut fleet = self.roster.iter().map(|&(_, r)| r).collect::<Vec<_>>();
+        fleet.push(self.report());
+        fleet.sort_by_key(|r| r.id);
+        fleet
+    }
+}
+
+fn is_duel(scenario: &str) -> bool {
+    matches!(scenario, "fighter_duel" | "frigate_duel" | "cruiser_duel")
+}
+
+fn class_rank(class: Class) -> u8 {
+    match class {
+        Class::Cruiser => 3,
+        Class::Frigate => 2,
+        Class::Fighter => 1,
+        _ => 0,
+    }
+}
+
+/// Cruisers hold back on point defence. Frigates screen a cruiser if there is one and
+/// strike otherwise. Fighters alternate between screen and strike in id order, and all
+/// strike when there is no capital ship to screen. In a duel there is no one to hold back
+/// for, so everything strikes.
+fn assign_role(fleet: &[RosterReport], ship: u32) -> Role {
+    if is_duel(scenario_name()) {
+        return Role::Strike;
+    }
+    let Some(report) = fleet.iter().find(|r| r.id == ship) else {
+        return Role::Strike;
+    };
+    let has = |class: Class| fleet.iter().any(|r| r.class == class);
+    match report.class {
+        Class::Cruiser => Role::PointDefence,
+        Class::Frigate if has(Class::Cruiser) => Role::Screen,
+        Class::Frigate => Role::Strike,
+        _ if !has(Class::Cruiser) && !has(Class::Frigate) => Role::Strike,
+        _ => {
+            let rank = fleet
+                .iter()
+                .filter(|r| r.class == report.class && r.id < ship)
+                .count();
+            if rank.is_multiple_of(2) {
+                Role::Screen
+            } else {
+                Role::Strike
+            }
+        }
+    }
+}
+
+/// The ship the screen protects: the heaviest in the fleet.
+fn guard(fleet: &[RosterReport]) -> Option<Vec2> {
+    fleet
+        .iter()
+        .filter(|r| class_rank(r.class) > 1)
+        .max_by_key(|r| (class_rank(r.class), std::cmp::Reverse(r.id)))
+        .map(|r| r.position)
+}
+
+/// The reported contact closest to the guarded ship, or to the fleet's first ship when
+/// there is nothing to guard.
+fn objective(fleet: &[RosterReport]) -> Option<Vec2> {
+    let reference = guard(fleet).or(fleet.first().map(|r| r.position))?;
+    fleet.iter().filter_map(|r| r.contact).min_by(|a, b| {
+        a.distance(reference)
+            .partial_cmp(&b.distance(reference))
+            .unwrap()
+    })
+}
@@ -1,7 +1,40 @@
+use crate::commander::Commander;
+use crate::fire_discipline::{FireControl, FiringSolution};
+use crate::launch_envelope::{can_launch, Envelope, MissileParams};
+use crate::messages::{LaunchOrder, Message, SalvoOrder};
+use crate::priority::{hit_probability, PriorityWeights};
+use crate::salvo::SalvoPlanner;
 use crate::target::{Target, TentativeTarget};
-use crate::utils::{angle_at_distance, send_class_and_position};
+use crate::threat::{assign_weapons, zone_station, WeaponSlot, Zone, Zones};
+use crate::utils::{
+    angle_at_distance, keep_station, plan_heading, send_class_and_position, threat_axis, Autopilot,
+    FriendlyHulls, OrientationGoal, OrientationWeights, BEACON_CHANNEL,
+};
 use oort_api::prelude::*;
 const TURRET_BULLET_SPEED: f64 = 2000.0;
+const BEACON_RADIO: usize = 7;
+const KILL_REPORT_TICKS: u32 = 10;
+const TRACK_BROADCAST_INTERVAL: u32 = 4;
+const MISSILE_CHANNELS: usize = 8;
+/// Fraction of the turret's reach to hold the main enemy capital ship at.
+const STANDOFF_FRACTION: f64 = 0.8;
+/// A kill to pass on to the missile channels that were ordering the salvo on it, as a bit
+/// per channel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct KillReport {
+    position: Vec2,
+    tick: u32,
+    channels: u8,
+}
+/// A missile or torpedo launched and still counted as flying, with the channel it was
+/// given at launch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct InFlight {
+    channel: usize,
+    /// Salvo group; torpedoes aren't part of salvos.
+    group: Option<u8>,
+    until: u32,
+}
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum CruiserRadarMode {
     FindNewTargets,
@@ -13,6 +46,16 @@ pub struct Cruiser {
     tentative_target: TentativeTarget,
     index: usize,
     radar_mode: CruiserRadarMode,
+    salvos: SalvoPlanner,
+    kill_reports: Vec<KillReport>,
+    in_flight: Vec<InFlight>,
+    /// Launches still to be announced on the beacon channel.
+    launches: Vec<LaunchOrder>,
+    station: Vec2,
+    commander: Commander,
+    fire_control: FireControl,
+    autopilot: Autopilot,
+    hulls: FriendlyHulls,
 }
 impl Default for Cruiser {
     fn default() -> Self {
@@ -27,77 +70,260 @@ impl Cruiser {
             tentative_target: TentativeTarget::new(),
             index: 0,
             radar_mode: CruiserRadarMode::FindNewTargets,
+            salvos: SalvoPlanner::new(),
+            kill_reports: Vec::new(),
+            in_flight: Vec::new(),
+            launches: Vec::new(),
+            station: position(),
+            commander: Commander::new(),
+            fire_control: FireControl::new(),
+            autopilot: Autopilot::new(),
+            hulls: FriendlyHulls::new(),
         }
     }
     pub fn tick(&mut self) {
-        select_radio(7);
-        set_radio_channel(9);
-        send_class_and_position();
+        self.receive_impacts();
+        self.salvos.tick();
+        self.kill_reports
+            .retain(|k| current_tick() < k.tick + KILL_REPORT_TICKS);
+        self.in_flight.retain(|m| current_tick() < m.until);
+        // The beacon radio also carries fleet command traffic in the fleet's command slot,
+        // launch orders on the other odd ticks when there are any, and the missiles on its
+        // channel the rest of the time.
+        let beacon_tick = current_tick().is_multiple_of(2);
+        let command_tick = Commander::is_command_tick();
+        let launch_tick = !beacon_tick && !command_tick && !self.launches.is_empty();
+        if beacon_tick {
+            select_radio(BEACON_RADIO);
+            set_radio_channel(BEACON_CHANNEL);
+            send_class_and_position();
+        } else if command_tick {
+            select_radio(BEACON_RADIO);
+            self.commander
+                .set_contact(self.targets.first().map(|t| t.position));
+            self.commander.transmit();
+        } else if launch_tick {
+            select_radio(BEACON_RADIO);
+            set_radio_channel(BEACON_CHANNEL);
+            Message::Launch(self.launches.remove(0)).send();
+        }
         debug!("targets {:?}", self.targets.len());
         debug!("index {:?}", self.index);
         debug!("radar_mode {:?}", self.radar_mode);
-        fire(1);
-        fire(2);
-        fire(3);
+        let zones = Zones::for_class(class());
+        let weights = PriorityWeights::current(self.commander.role());
+        let ships = self
+            .targets
+            .iter()
+            .filter(|t| {
+                t.validation.is_confirmed() && ![Class::Missile, Class::Torpedo].contains(&t.class)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        // Missiles take the long and medium zones, the turret the medium and short ones,
+        // where it is also the point defence.
+        let missile_slot = WeaponSlot {
+            index: 0,
+            zones: vec![Zone::Medium, Zone::Long],
+            point_defence: false,
+            bullet_speed: None,
+        };
+        let mut launch = LaunchOrder {
+            tick: current_tick(),
+            position: position(),
+            heading: heading(),
+            missiles: [None, None],
+            torpedo: None,
+        };
+        if can_launch(Class::Missile, &ships, Envelope::Reachable) {
+            if let Some(&(_, j)) =
+                assign_weapons(&self.targets, &[missile_slot], &zones, &weights).first()
+            {
+                let group = self.salvos.assign(&self.targets[j]);
+                let ready = [1, 2].iter().filter(|&&w| reload_ticks(w) == 0).count();
+                for side in 0..ready {
+                    let channel = self.launch_onto(Class::Missile, Some(group));
+                    launch.missiles[side] = Some((channel as u8, group));
+                }
+                if ready == 1 {
+                    launch.missiles[1] = launch.missiles[0];
+                }
+                fire(1);
+                fire(2);
+            }
+        }
+        let capital_ships = ships
+            .iter()
+            .filter(|t| [Class::Frigate, Class::Cruiser].contains(&t.class))
+            .cloned()
+            .collect::<Vec<_>>();
+        if can_launch(Class::Torpedo, &capital_ships, Envelope::NoEscape) {
+            if reload_ticks(3) == 0 {
+                launch.torpedo = Some(self.launch_onto(Class::Torpedo, None) as u8);
+            }
+            fire(3);
+        }
+        if launch.missiles[0].is_some() || launch.torpedo.is_some() {
+            self.launches.push(launch);
+        }
         debug!("gun reload {:?}", reload_ticks(0));
         debug!("port missile reload {:?}", reload_ticks(1));
         debug!("starboard missile reload {:?}", reload_ticks(2));
         debug!("torpedo reload {:?}", reload_ticks(3));
         self.update_targets();
         self.find_targets();
-        if !self.targets.is_empty() {
-            let furthest_index = self
-                .targets
-                .iter()
-                .enumerate()
-                .max_by(|(_, a), (_, b)| {
-                    position()
-                        .distance(a.position)
-                        .partial_cmp(&position().distance(b.position))
-                        .unwrap()
-                })
-                .unwrap_or((0, &self.targets[0]))
-                .0;
-            let above_targets = self
-                .targets
-                .iter()
-                .enumerate()
-                .filter(|(i, t)| {
-                    t.position.y >= 0.0 && (*i != furthest_index || reload_ticks(0) >= 150)
-                })
-                .collect::<Vec<(usize, &Target)>>();
-            let below_targets = self
-                .targets
-                .iter()
-                .enumerate()
-                .filter(|(i, t)| {
-                    t.position.y <= 0.0 && (*i != furthest_index || reload_ticks(0) >= 150)
-                })
-                .collect::<Vec<(usize, &Target)>>();
-            for i in 0..4 {
-                if !above_targets.is_empty() {
-                    let (j, t) = above_targets[i % above_targets.len()];
-                    select_radio(i);
-                    set_radio_channel(i);
-                    debug!("sending above {} to channel {}", j, i);
-                    send([t.position.x, t.position.y, t.velocity.x, t.velocity.y]);
-                }
-                if !below_targets.is_empty() {
-                    let (j, t) = below_targets[i % below_targets.len()];
-                    select_radio(i + 4);
-                    set_radio_channel(i + 4);
-                    debug!("sending below {} to channel {}", j, i + 4);
-                    send([t.position.x, t.position.y, t.velocity.x, t.velocity.y]);
-                }
+        // Each channel with missiles in flight carries their salvo's orders, split into as
+        // many approach slots as the salvo has missiles flying.
+        for channel in 0..MISSILE_CHANNELS {
+            if (beacon_tick || command_tick || launch_tick) && channel == BEACON_RADIO {
+                continue;
             }
-            let angle = self.lead_target(furthest_index, TURRET_BULLET_SPEED);
+            let Some(missile) = self
+                .in_flight
+                .iter()
+                .filter(|m| m.channel == channel)
+                .max_by_key(|m| m.group.is_some())
+                .copied()
+            else {
+                continue;
+            };
+            let order = missile.group.and_then(|group| {
+                let salvo = self.in_flight.iter().filter(|m| m.group == Some(group));
+                let size = salvo.clone().count();
+                let slot = salvo.filter(|m| m.channel < channel).count();
+                self.salvos
+                    .order(group, &self.targets, size as u8, slot as u8)
+            });
+            select_radio(channel);
+            set_radio_channel(channel);
+            self.broadcast(channel, order);
+        }
+        let turret = WeaponSlot {
+            index: 0,
+            zones: vec![Zone::Short, Zone::Medium],
+            point_defence: true,
+            bullet_speed: Some(TURRET_BULLET_SPEED),
+        };
+        if let Some(&(_, j)) = assign_weapons(&self.targets, &[turret], &zones, &weights).first() {
+            debug!(
+                "turret on {:?} in {:?}",
+                self.targets[j].class,
+                zones.zone(&self.targets[j])
+            );
+            let angle = self.lead_target(j, TURRET_BULLET_SPEED) + self.fire_control.dispersion(0);
             aim(0, angle);
-            fire(0);
+            let t = &self.targets[j];
+            let solution = FiringSolution {
+                aim_angle: angle,
+                target_class: t.class,
+                target_position: t.position,
+                target_velocity: t.velocity,
+                time_of_flight: position().distance(t.position) / TURRET_BULLET_SPEED,
+                hit_probability: hit_probability(t, TURRET_BULLET_SPEED),
+            };
+            self.fire_control.fire_at(0, &solution);
         }
+        self.manoeuvre(&zones);
+        let hull_heading = threat_axis(&self.targets).map(|axis| {
+            let goal = OrientationGoal {
+                assignments: Vec::new(),
+                threat_axis: Some(axis),
+                main_gun: None,
+            };
+            plan_heading(class(), &goal, &OrientationWeights::cruiser())
+        });
+        keep_station(&mut self.autopilot, &self.hulls, self.station, hull_heading);
         for (i, t) in self.targets.iter_mut().enumerate() {
             t.tick(i);
         }
     }
+    /// Moves the station to keep threats in the zones that favour the cruiser.
+    fn manoeuvre(&mut self, zones: &Zones) {
+        if let Some(station) = zone_station(&self.targets, zones, STANDOFF_FRACTION).or_else(|| {
+            self.commander
+                .ordered_station(zones.medium * STANDOFF_FRACTION)
+        }) {
+            self.station = station;
+        }
+        draw_triangle(self.station, 50.0, 0x00ffff);
+    }
+    /// Picks a channel for a missile of `class` being launched into salvo `group` and
+    /// counts it as in flight: the channel with the fewest launches still flying, keeping
+    /// off the beacon radio's channel while another is as free.
+    fn launch_onto(&mut self, class: Class, group: Option<u8>) -> usize {
+        let channel = (0..MISSILE_CHANNELS)
+            .min_by_key(|&c| {
+                let flying = self.in_flight.iter().filter(|m| m.channel == c).count();
+                (flying, c == BEACON_RADIO)
+            })
+            .unwrap();
+        let flight_time = MissileParams::for_class(class).max_flight_time;
+        self.in_flight.push(InFlight {
+            channel,
+            group,
+            until: current_tick() + (flight_time / TICK_LENGTH) as u32,
+        });
+        channel
+    }
+    /// Sends `order` on the selected radio, interleaved with the channel's other traffic:
+    /// every other tick a pending kill report for a salvo this channel was ordering, and
+    /// every `TRACK_BROADCAST_INTERVAL` ticks, on each channel in turn, a track from the
+    /// cruiser's picture for retargeting. A channel with no order to carry, such as a
+    /// torpedo's, gets a track every tick.
+    fn broadcast(&mut self, channel: usize, order: Option<SalvoOrder>) {
+        let kill = self
+            .kill_reports
+            .iter()
+            .rev()
+            .find(|k| k.channels & 1 << channel != 0);
+        let track_turn = current_tick().is_multiple_of(TRACK_BROADCAST_INTERVAL)
+            && (current_tick() / TRACK_BROADCAST_INTERVAL) as usize % MISSILE_CHANNELS == channel;
+        let track_index = if order.is_none() {
+            Some(current_tick() as usize)
+        } else if track_turn {
+            Some((current_tick() / TRACK_BROADCAST_INTERVAL) as usize / MISSILE_CHANNELS)
+        } else {
+            None
+        };
+        let track = track_index
+            .filter(|_| !self.targets.is_empty())
+            .map(|i| &self.targets[i % self.targets.len()]);
+        let message = if let Some(kill) = kill.filter(|_| current_tick().is_multiple_of(2)) {
+            Message::Kill {
+                position: kill.position,
+            }
+        } else if let Some(t) = track {
+            Message::Track {
+                class: t.class,
+                position: t.position,
+                velocity: t.velocity,
+            }
+        } else if let Some(order) = order {
+            Message::Order(order)
+        } else {
+            return;
+        };
+        debug!("channel {}: {:?}", channel, message);
+        message.send();
+    }
+    /// Reads every radio for missile impact reports. The beacon radio also carries the
+    /// other ships' beacons every other tick, which go to collision avoidance.
+    fn receive_impacts(&mut self) {
+        for i in 0..8 {
+            select_radio(i);
+            let Some(msg) = receive_bytes() else {
+                continue;
+            };
+            match Message::decode(&msg) {
+                Some(Message::Impact { group, .. }) => self.salvos.record_impact(group),
+                _ if i == BEACON_RADIO => {
+                    self.commander.listen(Some(&msg));
+                    self.hulls.hear_beacon(&msg);
+                }
+                _ => {}
+            }
+        }
+    }
     fn find_targets(&mut self) {
         select_radar(1);
         if let Some(contact) = scan() {
@@ -141,20 +367,35 @@ impl Cruiser {
             return;
         }
         if let Some(contact) = scan() {
-            set_radar_heading(contact.position.angle());
-            set_radar_width(angle_at_distance(
-                position().distance(contact.position),
-                100.0,
-            ));
-            set_radar_max_distance(contact.position.length() + 100.0);
-            set_radar_min_distance(contact.position.length() - 100.0);
+            let dp = contact.position - position();
+            set_radar_heading(dp.angle());
+            set_radar_width(angle_at_distance(dp.length(), 100.0));
+            set_radar_max_distance(dp.length() + 100.0);
+            set_radar_min_distance(dp.length() - 100.0);
             let target = &mut self.targets[self.index];
-            target.update(contact.position, contact.velocity);
+            target.observe(&contact);
+            self.fire_control
+                .observe(contact.class, contact.position, contact.velocity);
         } else {
             debug!("lost target: {:?}", self.index);
+            let lost_position = self.targets[self.index].position;
+            let gun_kill = self.fire_control.track_lost(lost_position);
+            if let Some(group) = self.salvos.track_lost(lost_position, gun_kill) {
+                let channels = self
+                    .in_flight
+                    .iter()
+                    .filter(|m| m.group == Some(group))
+                    .fold(0, |mask, m| mask | 1 << m.channel);
+                self.kill_reports.push(KillReport {
+                    position: lost_position,
+                    tick: current_tick(),
+                    channels,
+                });
+            }
             self.targets.remove(self.index);
+            // Step back so the advance below lands on the target that took its slot.
             if !self.targets.is_empty() {
-                self.index -= 1;
+                self.index = self.index.checked_sub(1).unwrap_or(self.targets.len() - 1);
             }
         }
         if !self.targets.is_empty() {
@@ -163,7 +404,10 @@ impl Cruiser {
         }
     }
     fn new_target(&mut self, new_position: Vec2, new_velocity: Vec2, new_class: Class) {
-        if new_class == Class::Missile || new_class == Class::Torpedo {
+        // Ordnance is only worth tracking once the turret can shoot it down.
+        if (new_class == Class::Missile || new_class == Class::Torpedo)
+            && position().distance(new_position) > Zones::for_class(class()).short
+        {
             return;
         }
         for t in &self.targets {
@@ -196,8 +440,9 @@ impl Cruiser {
             }
             future_position = new_future_position;
         }
+        future_position += self.fire_control.lead_correction(target.position);
         let color = 0x00ff00;
-        draw_polygon(future_position, 10.0, 4, 0.0, color);
-        (future_position - position()).angle()
+        draw_polygon(position() + future_position, 10.0, 4, 0.0, color);
+        future_position.angle()
     }
 }
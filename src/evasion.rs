@@ -0,0 +1,276 @@
+use crate::opponent::OpponentProfile;
+use crate::shot_prediction::DangerZone;
+use crate::utils::{class_guns, class_info, hull_bounding_radius, ThrustLimits, VecUtils};
+use oort_api::prelude::*;
+
+const DODGE_MARGIN: f64 = 10.0;
+/// How much earlier than strictly needed to start leaving a predicted shot's path.
+const DODGE_LEAD_TIME: f64 = 2.0 * TICK_LENGTH;
+/// How far out a closing missile starts to matter.
+const MISSILE_WARNING_TIME: f64 = 5.0;
+/// How long ordnance stays a threat after the radar last saw it.
+const ORDNANCE_MEMORY_TICKS: u32 = 60;
+/// A sighting this close to a remembered round's predicted position is the same round.
+const ORDNANCE_MATCH_DISTANCE: f64 = 100.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Threat {
+    /// An enemy ship whose guns may reach us.
+    Shooter {
+        class: Class,
+        position: Vec2,
+        velocity: Vec2,
+    },
+    Missile {
+        position: Vec2,
+        velocity: Vec2,
+    },
+    Bullet {
+        position: Vec2,
+        velocity: Vec2,
+    },
+    /// A shot predicted before it is fired.
+    Predicted(DangerZone),
+}
+
+impl Threat {
+    /// The threat a radar contact poses, if any. Asteroids, practice targets and unknowns
+    /// don't shoot back.
+    pub fn from_contact(contact: &ScanResult) -> Option<Threat> {
+        let (position, velocity) = (contact.position, contact.velocity);
+        match contact.class {
+            Class::Missile | Class::Torpedo => Some(Threat::Missile { position, velocity }),
+            Class::Fighter | Class::Frigate | Class::Cruiser => Some(Threat::Shooter {
+                class: contact.class,
+                position,
+                velocity,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// A dodge: the world-frame acceleration to apply and how soon the threat it answers
+/// would arrive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dodge {
+    pub acceleration: Vec2,
+    pub time_to_impact: f64,
+}
+
+/// Picks dodges against incoming fire. Bullets on a collision course are sidestepped,
+/// missiles are put on the beam, and ships whose guns can reach us are answered with
+/// random jinks timed off their bullets' flight time.
+pub struct Evasion {
+    jink: Vec2,
+    jink_until: u32,
+    opponent: Option<OpponentProfile>,
+    /// Ordnance seen lately, as position, velocity and the tick it was seen.
+    ordnance: Vec<(Vec2, Vec2, u32)>,
+}
+
+impl Default for Evasion {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Evasion {
+    pub fn new() -> Evasion {
+        Evasion {
+            jink: vec2(0.0, 0.0),
+            jink_until: 0,
+            opponent: None,
+            ordnance: Vec::new(),
+        }
+    }
+
+    /// Remembers ordnance the radar sweeps across, so every round closing on us is
+    /// dodged and not just the one the radar happens to be on.
+    pub fn observe(&mut self, contact: &ScanResult) {
+        self.ordnance
+            .retain(|&(_, _, tick)| current_tick() - tick < ORDNANCE_MEMORY_TICKS);
+        if !matches!(Threat::from_contact(contact), Some(Threat::Missile { .. })) {
+            return;
+        }
+        self.ordnance.retain(|&(position, velocity, tick)| {
+            let age = (current_tick() - tick) as f64 * TICK_LENGTH;
+            (position + velocity * age).distance(contact.position) > ORDNANCE_MATCH_DISTANCE
+        });
+        self.ordnance
+            .push((contact.position, contact.velocity, current_tick()));
+    }
+
+    /// Every remembered round, extrapolated to this tick.
+    pub fn ordnance_threats(&self) -> impl Iterator<Item = Threat> + '_ {
+        self.ordnance.iter().map(|&(position, velocity, tick)| {
+            let age = (current_tick() - tick) as f64 * TICK_LENGTH;
+            Threat::Missile {
+                position: position + velocity * age,
+                velocity,
+            }
+        })
+    }
+
+    /// How the shooter we are evading has been seen to fly, if known.
+    pub fn set_opponent(&mut self, profile: Option<OpponentProfile>) {
+        self.opponent = profile;
+    }
+
+    /// Dodge against the most imminent of `threats`, if any of them needs one.
+    pub fn evade(&mut self, threats: &[Threat]) -> Option<Dodge> {
+        let mut best: Option<Dodge> = None;
+        for threat in threats {
+            let dodge = match *threat {
+                Threat::Bullet { position, velocity } => sidestep(position, velocity),
+                Threat::Missile { position, velocity } => beam(position, velocity),
+                Threat::Shooter {
+                    class,
+                    position,
+                    velocity,
+                } => self.jink(class, position, velocity),
+                Threat::Predicted(zone) => avoid_zone(&zone),
+            };
+            if let Some(dodge) = dodge {
+                if best.is_none_or(|b| dodge.time_to_impact < b.time_to_impact) {
+                    best = Some(dodge);
+                }
+            }
+        }
+        best
+    }
+
+    /// Changes acceleration at random intervals no longer than the enemy's time of flight,
+    /// so that whatever it leads us by has gone stale by the time the shot arrives. The
+    /// interval is never shorter than the time it takes to slide a hull-width sideways.
+    /// Against an opponent seen to turn slowly it can stretch by the time the opponent
+    /// needs to swing its nose onto our new track.
+    fn jink(
+        &mut self,
+        their_class: Class,
+        their_position: Vec2,
+        their_velocity: Vec2,
+    ) -> Option<Dodge> {
+        let gun = class_guns(their_class)
+            .into_iter()
+            .max_by(|a, b| a.range().partial_cmp(&b.range()).unwrap())?;
+        let dp = position() - their_position;
+        let dv = velocity() - their_velocity;
+        let flight_time =
+            time_of_flight(dp, dv, gun.bullet_speed).filter(|&t| t < gun.bullet_lifetime)?;
+        let lateral = class_info(class()).lateral_acceleration;
+        let dodge_time = (2.0 * (hull_radius() + DODGE_MARGIN) / lateral).sqrt();
+        if current_tick() >= self.jink_until {
+            let across = vec2(-dp.y, dp.x).normalize() * rand(-1.0, 1.0).signum();
+            let along = dp.normalize() * rand(-0.5, 0.5);
+            self.jink = (across + along).normalize();
+            let reaction = self.opponent.map_or(0.0, |p| {
+                p.reaction_time((hull_radius() + DODGE_MARGIN) / dp.length())
+            });
+            let period = rand(dodge_time, flight_time.max(dodge_time) + reaction);
+            self.jink_until = current_tick() + (period / TICK_LENGTH).ceil() as u32;
+        }
+        Some(Dodge {
+            acceleration: full_thrust(self.jink),
+            time_to_impact: flight_time,
+        })
+    }
+}
+
+/// Time for a bullet fired at `speed` from a gun offset `-dp` from us, with `dv` our
+/// velocity relative to the gun, to reach us.
+pub fn time_of_flight(dp: Vec2, dv: Vec2, speed: f64) -> Option<f64> {
+    let a = dv.dot(dv) - speed * speed;
+    let b = 2.0 * dp.dot(dv);
+    let c = dp.dot(dp);
+    if a.abs() < 1e-9 {
+        return Some(-c / b).filter(|&t| t > 0.0);
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    [(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)]
+        .into_iter()
+        .filter(|&t| t > 0.0)
+        .min_by(|a, b| a.partial_cmp(b).unwrap())
+}
+
+/// Moves off the track of a bullet that would otherwise hit, toward whichever side it
+/// was already going to miss on.
+fn sidestep(their_position: Vec2, their_velocity: Vec2) -> Option<Dodge> {
+    let dp = position() - their_position;
+    let dv = velocity() - their_velocity;
+    let closest_time = -dp.dot(dv) / dv.square_magnitude().max(1e-9);
+    if closest_time <= 0.0 {
+        return None;
+    }
+    let miss = dp + dv * closest_time;
+    if miss.length() > hull_radius() + DODGE_MARGIN {
+        return None;
+    }
+    let direction = if miss.length() > 1e-3 {
+        miss.normalize()
+    } else {
+        vec2(-their_velocity.y, their_velocity.x).normalize()
+    };
+    Some(Dodge {
+        acceleration: full_thrust(direction),
+        time_to_impact: closest_time,
+    })
+}
+
+/// Leaves a predicted shot's danger zone sideways to the bullet, starting no earlier than
+/// it takes to slide out of it, so the shooter's lead has as long as possible to go stale.
+fn avoid_zone(zone: &DangerZone) -> Option<Dodge> {
+    let offset = position() + velocity() * zone.time - zone.center;
+    let across = offset - zone.direction * offset.dot(zone.direction);
+    if across.length() > zone.radius {
+        return None;
+    }
+    let lateral = class_info(class()).lateral_acceleration;
+    let clear_time = (2.0 * (zone.radius - across.length()) / lateral).sqrt();
+    if zone.time > clear_time + DODGE_LEAD_TIME {
+        return None;
+    }
+    let direction = if across.length() > 1e-3 {
+        across.normalize()
+    } else {
+        let side = vec2(-zone.direction.y, zone.direction.x);
+        side * velocity().dot(side).signum()
+    };
+    Some(Dodge {
+        acceleration: full_thrust(direction),
+        time_to_impact: zone.time,
+    })
+}
+
+/// Accelerates square to a closing missile's line of sight, on the side we are already
+/// drifting toward, to make it turn as hard as possible.
+fn beam(their_position: Vec2, their_velocity: Vec2) -> Option<Dodge> {
+    let dp = position() - their_position;
+    let dv = velocity() - their_velocity;
+    let closing_speed = -dp.dot(dv) / dp.length();
+    if closing_speed <= 0.0 {
+        return None;
+    }
+    let time_to_impact = dp.length() / closing_speed;
+    if time_to_impact > MISSILE_WARNING_TIME {
+        return None;
+    }
+    let across = vec2(-dp.y, dp.x).normalize();
+    let side = if dv.dot(across) < 0.0 { -1.0 } else { 1.0 };
+    Some(Dodge {
+        acceleration: full_thrust(across * side),
+        time_to_impact,
+    })
+}
+
+fn full_thrust(direction: Vec2) -> Vec2 {
+    direction * ThrustLimits::current().along(direction, heading())
+}
+
+fn hull_radius() -> f64 {
+    hull_bounding_radius(class()).unwrap_or(10.0)
+}
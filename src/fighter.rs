@@ -1,72 +1,313 @@
-use crate::pid::PID;
+use crate::behaviour::{condition, selector, sequence, succeed, Node, Status, Tree};
+use crate::commander::{Commander, Role};
+use crate::evasion::{Dodge, Evasion, Threat};
+use crate::fire_discipline::{FireControl, FiringSolution};
+use crate::formation::{Formation, FormationShape};
+use crate::kiting::kite_station;
+use crate::launch_envelope::{can_launch, Envelope};
+use crate::messages::Message;
+use crate::opponent::OpponentModel;
+use crate::priority::dodge_hit_probability;
+use crate::shot_prediction::ShotPredictor;
+use crate::target::Target;
 use crate::utils::VecUtils;
-use crate::utils::{angle_at_distance, draw_curve, draw_heading, send_class_and_position, turn_to};
+use crate::utils::{
+    angle_at_distance, class_max_acceleration, draw_curve, draw_heading, send_class_and_position,
+    thrust, Autopilot, FriendlyHulls, Mpc, MpcGoal, MpcWeights, TrajectoryGoal, BEACON_CHANNEL,
+};
 use oort_api::prelude::*;
 use std::collections::VecDeque;
+
+/// Further than this from the kiting station, steer for it rather than leaving the
+/// controller free to aim and dodge.
+const KITE_TOLERANCE: f64 = 300.0;
+/// Below this health a fighter breaks off and returns to the cruiser.
+const RTB_HEALTH: f64 = 30.0;
+/// How far short of the cruiser a returning fighter holds.
+const RTB_STANDOFF: f64 = 300.0;
+/// A screening fighter only engages contacts this close to the ship it guards.
+const SCREEN_RADIUS: f64 = 3000.0;
+/// One tick in this many the radar leaves the contact to look for its bullets.
+const BULLET_SWEEP_INTERVAL: u32 = 8;
+/// Beam width for the bullet sweep, wide enough to take in shots led well off the line of
+/// sight.
+const BULLET_SWEEP_WIDTH: f64 = TAU / 60.0;
+
 pub struct Fighter {
-    move_to: Vec2,
+    tree: Tree<FighterState>,
+    state: FighterState,
+}
+
+/// The blackboard a fighter's behaviour tree works on.
+pub struct FighterState {
+    /// This tick's radar contact, if it isn't debris.
+    pub contact: Option<ScanResult>,
     last_velocity: Option<Vec2>,
     accelerations: VecDeque<Vec2>,
     predictions: VecDeque<Vec2>,
     real_positions: VecDeque<Vec2>,
-    pid: PID,
+    mpc: Mpc,
+    evasion: Evasion,
+    formation: Formation,
+    commander: Commander,
+    opponents: OpponentModel,
+    shots: ShotPredictor,
+    fire_control: FireControl,
+    autopilot: Autopilot,
+    hulls: FriendlyHulls,
+    /// This tick's dodge, if `Evade` found one.
+    dodge: Option<Dodge>,
+    /// Whether this tick's scan was the bullet sweep rather than the contact.
+    bullet_sweep: bool,
 }
+
 impl Fighter {
     pub fn new() -> Fighter {
-        let pid = PID::new(
-            50.0,
-            0.0,
-            1000.0 / 60.0,
-            max_angular_acceleration(),
-            max_angular_acceleration(),
-        );
+        Fighter::with_tree(match scenario_name() {
+            "fighter_duel" => duel_tree(),
+            _ => default_tree(),
+        })
+    }
+    pub fn with_tree(tree: Tree<FighterState>) -> Fighter {
         Fighter {
-            move_to: position(),
-            last_velocity: None,
-            accelerations: VecDeque::new(),
-            pid,
-            predictions: VecDeque::new(),
-            real_positions: VecDeque::new(),
+            tree,
+            state: FighterState::new(),
         }
     }
     pub fn tick(&mut self) {
+        let state = &mut self.state;
+        // Radio 1 carries the hull beacon every other tick, and formation reports and
+        // fleet command traffic in between.
         select_radio(1);
-        set_radio_channel(9);
-        send_class_and_position();
+        let msg = receive_bytes();
+        state.formation.listen(msg.as_ref());
+        state.commander.listen(msg.as_ref());
+        if let Some(msg) = &msg {
+            state.hulls.hear_beacon(msg);
+        }
+        if Commander::is_command_tick() {
+            state.commander.transmit();
+        } else if current_tick() % 4 == 1 {
+            state.formation.announce();
+        } else {
+            set_radio_channel(BEACON_CHANNEL);
+            send_class_and_position();
+        }
         select_radio(0);
         set_radio_channel(0);
-        debug!("Hello from fighter.rs");
-        fire(1);
-        fire(0);
-        let (target, target_velocity) = if let Some(contact) = scan() {
-            (contact.position, contact.velocity)
+        let contact = scan();
+        if let Some(contact) = &contact {
+            state.hulls.observe_debris(contact);
+            state.shots.observe(contact);
+            state.evasion.observe(contact);
+        }
+        if state.bullet_sweep {
+            // The beam was off the contact, so carry its track over from last tick.
+            state.bullet_sweep = false;
+            state.contact = state.contact.map(|c| ScanResult {
+                position: c.position + c.velocity * TICK_LENGTH,
+                ..c
+            });
         } else {
-            fire(0);
-            set_radar_heading(radar_heading() + radar_width());
-            self.pid.reset();
-            self.last_velocity = None;
-            self.accelerations.clear();
-            set_radar_width(TAU / 30.0);
-            set_radar_max_distance(1e9);
-            set_radar_min_distance(0.0);
-            return;
+            state.contact = contact.filter(|c| c.class != Class::Asteroid);
+            if let Some(c) = state.contact {
+                state.opponents.observe(c.class, c.position, c.velocity);
+            }
+        }
+        state.formation.set_engaged(state.contact.is_some());
+        state
+            .commander
+            .set_contact(state.contact.map(|c| c.position));
+        let status = self.tree.tick(state);
+        debug!("behaviour {:?}", status);
+    }
+}
+
+/// Returns to the cruiser when damaged, otherwise engages whatever the radar holds that
+/// its role allows, and with nothing to engage follows the fleet's orders or keeps
+/// formation while searching.
+pub fn default_tree() -> Tree<FighterState> {
+    selector(vec![
+        sequence(vec![
+            condition(|_| health() < RTB_HEALTH),
+            succeed(Box::new(Evade)),
+            Box::new(ReturnToBase),
+        ]),
+        sequence(vec![
+            condition(in_remit),
+            succeed(Box::new(Evade)),
+            Box::new(Engage),
+        ]),
+        sequence(vec![
+            succeed(selector(vec![Box::new(FollowOrders), Box::new(Regroup)])),
+            Box::new(Search),
+        ]),
+    ])
+}
+
+/// Whether the contact is ours to engage: a screening fighter leaves anything far from the
+/// ship it guards to the strikers.
+fn in_remit(state: &FighterState) -> bool {
+    match (
+        state.commander.role(),
+        state.commander.guard(),
+        state.contact,
+    ) {
+        (Role::Screen, Some(guard), Some(contact)) => {
+            contact.position.distance(guard) < SCREEN_RADIUS
+        }
+        _ => true,
+    }
+}
+
+/// One on one there is no one to regroup with or return to.
+pub fn duel_tree() -> Tree<FighterState> {
+    selector(vec![
+        sequence(vec![succeed(Box::new(Evade)), Box::new(Engage)]),
+        Box::new(Search),
+    ])
+}
+
+/// Sweeps the radar for a contact. Keeps running until one turns up.
+pub struct Search;
+
+impl Node<FighterState> for Search {
+    fn tick(&mut self, state: &mut FighterState) -> Status {
+        set_radar_heading(radar_heading() + radar_width());
+        set_radar_width(TAU / 30.0);
+        set_radar_max_distance(1e9);
+        set_radar_min_distance(0.0);
+        state.last_velocity = None;
+        state.accelerations.clear();
+        Status::Running
+    }
+}
+
+/// Flies to this fighter's formation station. Fails when there is no one to fly with.
+pub struct Regroup;
+
+impl Node<FighterState> for Regroup {
+    fn tick(&mut self, state: &mut FighterState) -> Status {
+        match state.formation.station() {
+            Some(station) => {
+                state.autopilot.fly(&station, &state.hulls);
+                Status::Success
+            }
+            None => Status::Failure,
+        }
+    }
+}
+
+/// Flies to where the fleet commander's orders put this fighter: strikers to the
+/// objective, screens out in front of the ship they guard. Fails without orders to follow.
+pub struct FollowOrders;
+
+impl Node<FighterState> for FollowOrders {
+    fn tick(&mut self, state: &mut FighterState) -> Status {
+        let goal = match state.commander.role() {
+            Role::Strike => state.commander.objective().map(TrajectoryGoal::fly_through),
+            Role::Screen => state
+                .commander
+                .ordered_station(0.0)
+                .map(TrajectoryGoal::stop_at),
+            Role::PointDefence => None,
         };
-        set_radar_width(angle_at_distance(position().distance(target), 100.0));
+        match goal {
+            Some(goal) => {
+                state.autopilot.fly(&goal, &state.hulls);
+                Status::Success
+            }
+            None => Status::Failure,
+        }
+    }
+}
+
+/// Tracks, leads and shoots the radar contact while holding the formation station or the
+/// kiting range, or making this tick's dodge. The MPC does all the turning and thrusting.
+/// Fails without a contact.
+pub struct Engage;
+
+impl Node<FighterState> for Engage {
+    fn tick(&mut self, state: &mut FighterState) -> Status {
+        let Some(contact) = state.contact else {
+            return Status::Failure;
+        };
+        let target = contact.position;
+        let target_velocity = contact.velocity;
         set_radar_heading(target.angle_to(position()));
-        set_radar_max_distance(position().distance(target) + 100.0);
-        set_radar_min_distance(position().distance(target) - 100.0);
-        send([target.x, target.y, target_velocity.x, target_velocity.y]);
-        let predicted_position = self.lead_target(target, target_velocity, 1000.0);
-        self.real_positions.push_back(target);
-        if self.real_positions.len() > 300 {
-            self.real_positions.pop_front();
-        }
-        draw_curve(&self.predictions, 0xff0000, false);
-        draw_curve(&self.real_positions, 0x00ff00, false);
+        if (current_tick() + 1).is_multiple_of(BULLET_SWEEP_INTERVAL)
+            && position().distance(target) > 100.0
+        {
+            // Look between us and the contact for the bullets it has fired at us, so the
+            // shot predictor can pin down its reload phase. Too close in, the contact's
+            // own return would fill the beam, so skip it.
+            state.bullet_sweep = true;
+            set_radar_width(BULLET_SWEEP_WIDTH);
+            set_radar_max_distance(position().distance(target) - 100.0);
+            set_radar_min_distance(0.0);
+        } else {
+            set_radar_width(angle_at_distance(position().distance(target), 100.0));
+            set_radar_max_distance(position().distance(target) + 100.0);
+            set_radar_min_distance(position().distance(target) - 100.0);
+        }
+        Message::Target {
+            position: target,
+            velocity: target_velocity,
+        }
+        .send();
+        let profile = state.opponents.profile(contact.class, target);
+        let trust = profile.map_or(1.0, |p| p.acceleration_trust());
+        state
+            .fire_control
+            .observe(contact.class, target, target_velocity);
+        let predicted_position = state.lead_target(target, target_velocity, 1000.0, trust)
+            + state.fire_control.lead_correction(target);
+        state.real_positions.push_back(target);
+        if state.real_positions.len() > 300 {
+            state.real_positions.pop_front();
+        }
+        draw_curve(&state.predictions, 0xff0000, false);
+        draw_curve(&state.real_positions, 0x00ff00, false);
         let angle = predicted_position.angle();
-        let random_offset = rand(-1.0, 1.0) * TAU / 240.0;
-        turn_to(angle + random_offset);
-        fire(0);
+        let dispersion = state.fire_control.dispersion(0);
+        let kite = kite_station(contact.class, target, target_velocity, profile.as_ref());
+        draw_triangle(kite.position, 20.0, 0xffff00);
+        let station = state
+            .formation
+            .station()
+            .or(Some(kite).filter(|k| position().distance(k.position) > KITE_TOLERANCE));
+        state.mpc.step(
+            &MpcGoal {
+                aim: predicted_position.rotate(dispersion),
+                target_position: target,
+                target_velocity,
+                station,
+                evasion: state.dodge.map(|d| d.acceleration),
+            },
+            &state.hulls,
+        );
+        let time_of_flight = predicted_position.length() / 1000.0;
+        let dodge = profile.map_or(class_max_acceleration(contact.class), |p| {
+            p.dodge_acceleration()
+        });
+        let hit_probability = dodge_hit_probability(contact.class, time_of_flight, dodge);
+        let solution = FiringSolution {
+            aim_angle: heading(),
+            target_class: contact.class,
+            target_position: target,
+            target_velocity,
+            time_of_flight,
+            hit_probability,
+        };
+        if state.fire_control.fire_at(0, &solution) {
+            state.opponents.shot_fired();
+        }
+        let missile_target = Target::new(target, target_velocity, contact.class);
+        if reload_ticks(1) == 0
+            && can_launch(Class::Missile, &[missile_target], Envelope::Reachable)
+        {
+            fire(1);
+        }
         if angle_diff(heading(), target_velocity.angle()).abs() < 0.1
             && angle_diff(heading(), angle).abs() < 0.1
         {
@@ -74,16 +315,107 @@ impl Fighter {
         } else {
             deactivate_ability(Ability::Boost);
         }
-        self.move_to = target;
-        let acceleration_vector =
-            vec2(max_forward_acceleration(), 0.0).rotate((self.move_to - position()).angle());
-        accelerate(acceleration_vector);
+        Status::Success
+    }
+}
+
+/// Works out a dodge of the contact's fire, of any ordnance seen lately or of shots
+/// predicted from the shooters seen lately, and leaves it for whichever node thrusts this
+/// tick. Fails when there is nothing worth dodging.
+pub struct Evade;
+
+impl Node<FighterState> for Evade {
+    fn tick(&mut self, state: &mut FighterState) -> Status {
+        let mut threats = state
+            .shots
+            .danger_zones()
+            .into_iter()
+            .map(Threat::Predicted)
+            .chain(state.evasion.ordnance_threats())
+            .collect::<Vec<_>>();
+        if let Some(contact) = &state.contact {
+            state
+                .evasion
+                .set_opponent(state.opponents.profile(contact.class, contact.position));
+            threats.extend(
+                Threat::from_contact(contact).filter(|t| matches!(t, Threat::Shooter { .. })),
+            );
+        }
+        state.dodge = state.evasion.evade(&threats);
+        if state.dodge.is_some() {
+            Status::Success
+        } else {
+            Status::Failure
+        }
+    }
+}
+
+/// Flies back to the nearest cruiser heard on the beacon channel and holds just short of
+/// it, breaking off for any dodge `Evade` found. Fails when no cruiser is known.
+pub struct ReturnToBase;
+
+impl Node<FighterState> for ReturnToBase {
+    fn tick(&mut self, state: &mut FighterState) -> Status {
+        let Some(cruiser) = state
+            .hulls
+            .obstacles()
+            .iter()
+            .copied()
+            .filter(|o| o.class == Class::Cruiser)
+            .min_by(|a, b| {
+                position()
+                    .distance(a.position)
+                    .partial_cmp(&position().distance(b.position))
+                    .unwrap()
+            })
+        else {
+            return Status::Failure;
+        };
+        let approach = (position() - cruiser.position).normalize();
+        match state.dodge {
+            Some(dodge) => thrust(state.hulls.avoid(dodge.acceleration)),
+            None => state.autopilot.fly(
+                &TrajectoryGoal::rendezvous(
+                    cruiser.position + approach * RTB_STANDOFF,
+                    cruiser.velocity,
+                ),
+                &state.hulls,
+            ),
+        }
+        deactivate_ability(Ability::Boost);
+        Status::Success
+    }
+}
+
+impl FighterState {
+    fn new() -> FighterState {
+        FighterState {
+            contact: None,
+            last_velocity: None,
+            accelerations: VecDeque::new(),
+            predictions: VecDeque::new(),
+            real_positions: VecDeque::new(),
+            mpc: Mpc::new(class(), MpcWeights::fighter()),
+            evasion: Evasion::new(),
+            formation: Formation::new(FormationShape::Wedge),
+            commander: Commander::new(),
+            opponents: OpponentModel::new(),
+            shots: ShotPredictor::new(),
+            fire_control: FireControl::new(),
+            autopilot: Autopilot::new(),
+            hulls: FriendlyHulls::new(),
+            dodge: None,
+            bullet_sweep: false,
+        }
     }
+    /// Aim point for a bullet at `bullet_speed`, extrapolating the target's recent
+    /// acceleration scaled by `acceleration_trust`.
     fn lead_target(
         &mut self,
         target_position: Vec2,
         target_velocity: Vec2,
         bullet_speed: f64,
+        acceleration_trust: f64,
     ) -> Vec2 {
         let delta_position = target_position - position();
         let delta_velocity = target_velocity - velocity();
@@ -101,7 +433,7 @@ impl Fighter {
         for a in self.accelerations.iter() {
             acceleration += a;
         }
-        acceleration /= self.accelerations.len() as f64;
+        acceleration *= acceleration_trust / self.accelerations.len() as f64;
         let mut prediction = delta_position;
         let mut time_to_target = 0.0;
         for i in 0..100 {
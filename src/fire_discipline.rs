@@ -0,0 +1,169 @@
+use crate::rounds::{Round, RoundTracker};
+use crate::utils::{class_guns, gun_offsets};
+use oort_api::prelude::*;
+
+/// How a gun is fired: bursts of `burst` rounds walked across `dispersion` radians either
+/// side of the lead, `pause_ticks` between bursts, and no shot below
+/// `min_hit_probability`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Discipline {
+    pub burst: u32,
+    pub pause_ticks: u32,
+    pub dispersion: f64,
+    pub min_hit_probability: f64,
+}
+
+impl Discipline {
+    /// Settings for weapon `gun` of `class`: fast-firing guns walk bursts across the
+    /// target's dodge, the frigate's main gun takes single aimed shots.
+    pub fn for_gun(class: Class, gun: usize) -> Discipline {
+        match (class, gun) {
+            (Class::Fighter, _) => Discipline {
+                burst: 6,
+                pause_ticks: 4,
+                dispersion: TAU / 240.0,
+                min_hit_probability: 0.02,
+            },
+            (Class::Frigate, 0) => Discipline {
+                burst: 1,
+                pause_ticks: 0,
+                dispersion: 0.0,
+                min_hit_probability: 0.05,
+            },
+            (Class::Frigate, _) => Discipline {
+                burst: 8,
+                pause_ticks: 8,
+                dispersion: TAU / 360.0,
+                min_hit_probability: 0.05,
+            },
+            _ => Discipline {
+                burst: 3,
+                pause_ticks: 0,
+                dispersion: TAU / 720.0,
+                min_hit_probability: 0.02,
+            },
+        }
+    }
+}
+
+/// A shot a gun has been laid for: where it points and the target it is meant to hit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FiringSolution {
+    pub aim_angle: f64,
+    pub target_class: Class,
+    pub target_position: Vec2,
+    pub target_velocity: Vec2,
+    pub time_of_flight: f64,
+    pub hit_probability: f64,
+}
+
+/// Rough hits from our guns that kill a `class` ship. Capital ships take more than any
+/// burst carries, so fire on them never ceases.
+fn hits_to_kill(class: Class) -> f64 {
+    match class {
+        Class::Missile | Class::Torpedo => 1.0,
+        Class::Fighter => 3.0,
+        _ => f64::INFINITY,
+    }
+}
+
+/// Fires this ship's guns under their `Discipline`, keeping track of every round so a
+/// target that is as good as dead isn't fired on again and misses correct the lead.
+#[derive(Debug)]
+pub struct FireControl {
+    disciplines: Vec<Discipline>,
+    fired_in_burst: Vec<u32>,
+    resume_tick: Vec<u32>,
+    rounds: RoundTracker,
+}
+
+impl Default for FireControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FireControl {
+    pub fn new() -> FireControl {
+        let disciplines = (0..class_guns(class()).len())
+            .map(|gun| Discipline::for_gun(class(), gun))
+            .collect::<Vec<_>>();
+        FireControl {
+            fired_in_burst: vec![0; disciplines.len()],
+            resume_tick: vec![0; disciplines.len()],
+            disciplines,
+            rounds: RoundTracker::new(),
+        }
+    }
+
+    pub fn set_discipline(&mut self, gun: usize, discipline: Discipline) {
+        self.disciplines[gun] = discipline;
+    }
+
+    /// Angle to add to the lead for `gun`'s next round, walking from one side of the
+    /// dispersion to the other over a burst.
+    pub fn dispersion(&self, gun: usize) -> f64 {
+        let d = &self.disciplines[gun];
+        if d.burst < 2 {
+            return 0.0;
+        }
+        let step = self.fired_in_burst[gun] as f64 / (d.burst - 1) as f64;
+        d.dispersion * (2.0 * step - 1.0)
+    }
+
+    /// Resolves the rounds fired at a track as it is seen again.
+    pub fn observe(&mut self, class: Class, their_position: Vec2, their_velocity: Vec2) {
+        self.rounds.observe(class, their_position, their_velocity);
+    }
+
+    /// Forgets the track lost at `last_position`. Returns whether our rounds killed it.
+    pub fn track_lost(&mut self, last_position: Vec2) -> bool {
+        self.rounds.track_lost(last_position)
+    }
+
+    /// Offset to add to the lead on the track at `their_position`.
+    pub fn lead_correction(&self, their_position: Vec2) -> Vec2 {
+        self.rounds.correction(their_position)
+    }
+
+    /// Fires `gun` on `solution` if it has reloaded, isn't between bursts, the shot is
+    /// likely enough to hit and the rounds already on their way aren't enough to kill the
+    /// target. Returns whether it fired.
+    pub fn fire_at(&mut self, gun: usize, solution: &FiringSolution) -> bool {
+        let d = self.disciplines[gun];
+        if reload_ticks(gun) > 0 || current_tick() < self.resume_tick[gun] {
+            return false;
+        }
+        let hit_probability = self
+            .rounds
+            .hit_probability(solution.target_position, solution.hit_probability);
+        if hit_probability < d.min_hit_probability {
+            debug!("gun {} holding fire: p(hit) {:.3}", gun, hit_probability);
+            return false;
+        }
+        let expected_hits = self.rounds.expected_hits(solution.target_position);
+        if expected_hits >= hits_to_kill(solution.target_class) {
+            debug!("gun {} ceasing fire: target likely dead", gun);
+            return false;
+        }
+        fire(gun);
+        let bullet_speed = class_guns(class())[gun].bullet_speed;
+        self.rounds.fired(Round {
+            weapon: gun,
+            origin: position() - gun_offsets(gun).rotate(heading()),
+            velocity: velocity() + vec2(bullet_speed, 0.0).rotate(solution.aim_angle),
+            fired_tick: current_tick(),
+            impact_tick: current_tick() + (solution.time_of_flight / TICK_LENGTH).ceil() as u32,
+            target_class: solution.target_class,
+            target_position: solution.target_position,
+            target_velocity: solution.target_velocity,
+            hit_probability,
+        });
+        self.fired_in_burst[gun] += 1;
+        if self.fired_in_burst[gun] >= d.burst {
+            self.fired_in_burst[gun] = 0;
+            self.resume_tick[gun] = current_tick() + d.pause_ticks;
+        }
+        true
+    }
+}
@@ -0,0 +1,134 @@
+use crate::messages::{FormationReport, Message};
+use crate::utils::TrajectoryGoal;
+use oort_api::prelude::*;
+
+pub const FORMATION_CHANNEL: usize = 8;
+const SPACING: f64 = 150.0;
+const MEMBER_TIMEOUT_TICKS: u32 = 60;
+/// Below this speed a ship's velocity is too noisy to orient the formation by.
+const MIN_FRAME_SPEED: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FormationShape {
+    LineAbreast,
+    Trail,
+    Wedge,
+}
+
+impl FormationShape {
+    pub fn from_u8(value: u8) -> FormationShape {
+        match value {
+            0 => FormationShape::LineAbreast,
+            1 => FormationShape::Trail,
+            _ => FormationShape::Wedge,
+        }
+    }
+
+    /// Station of the ship in `slot` relative to the one it keeps station on, in that
+    /// ship's frame: x ahead, y to the left. Slots alternate left and right.
+    pub fn offset(&self, slot: usize) -> Vec2 {
+        let rank = slot.div_ceil(2) as f64;
+        let side = if slot % 2 == 1 { 1.0 } else { -1.0 };
+        match self {
+            FormationShape::LineAbreast => vec2(0.0, side * rank * SPACING),
+            FormationShape::Trail => vec2(-(slot as f64) * SPACING, 0.0),
+            FormationShape::Wedge => vec2(-rank * SPACING, side * rank * SPACING),
+        }
+    }
+}
+
+/// Formation membership worked out over the radio. Every member reports its state on
+/// `FORMATION_CHANNEL`, the lowest id heard leads, and the others take slots in id order.
+/// While the leader is engaged the formation breaks into pairs: even slots fight freely
+/// and each odd slot flies as wingman to the slot before it.
+pub struct Formation {
+    shape: FormationShape,
+    engaged: bool,
+    members: Vec<(u32, FormationReport)>,
+}
+
+impl Formation {
+    pub fn new(shape: FormationShape) -> Formation {
+        Formation {
+            shape,
+            engaged: false,
+            members: Vec::new(),
+        }
+    }
+
+    /// Takes in whatever the radio carrying formation reports heard this tick.
+    pub fn listen(&mut self, msg: Option<&[u8; 32]>) {
+        self.members
+            .retain(|(tick, _)| current_tick() - tick < MEMBER_TIMEOUT_TICKS);
+        if let Some(Message::Formation(report)) = msg.and_then(Message::decode) {
+            self.members.retain(|(_, m)| m.id != report.id);
+            self.members.push((current_tick(), report));
+        }
+    }
+
+    /// Tunes the selected radio to the formation channel and reports our state.
+    pub fn announce(&self) {
+        set_radio_channel(FORMATION_CHANNEL);
+        Message::Formation(self.report()).send();
+    }
+
+    pub fn set_engaged(&mut self, engaged: bool) {
+        self.engaged = engaged;
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.slot() == 0
+    }
+
+    /// Where to fly to hold our station, or `None` when we lead the formation or, while
+    /// engaged, our pair.
+    pub fn station(&self) -> Option<TrajectoryGoal> {
+        let slot = self.slot();
+        if slot == 0 {
+            return None;
+        }
+        let ranked = self.ranked();
+        let leader = ranked[0];
+        let (reference, offset) = if leader.1.engaged {
+            if slot.is_multiple_of(2) {
+                return None;
+            }
+            (ranked[slot - 1], FormationShape::Wedge.offset(1))
+        } else {
+            (leader, leader.1.shape.offset(slot))
+        };
+        let (tick, report) = reference;
+        let age = (current_tick() - tick + 1) as f64 * TICK_LENGTH;
+        let frame = if report.velocity.length() > MIN_FRAME_SPEED {
+            report.velocity.angle()
+        } else {
+            report.heading
+        };
+        let station = report.position + report.velocity * age + offset.rotate(frame);
+        draw_line(position(), station, 0x4444ff);
+        Some(TrajectoryGoal::rendezvous(station, report.velocity))
+    }
+
+    fn report(&self) -> FormationReport {
+        FormationReport {
+            id: id(),
+            shape: self.shape,
+            engaged: self.engaged,
+            position: position(),
+            velocity: velocity(),
+            heading: heading(),
+        }
+    }
+
+    /// Every member including us, sorted by id, so the index is the slot.
+    fn ranked(&self) -> Vec<(u32, FormationReport)> {
+        let mut ranked = self.members.clone();
+        ranked.push((current_tick(), self.report()));
+        ranked.sort_by_key(|(_, m)| m.id);
+        ranked
+    }
+
+    fn slot(&self) -> usize {
+        self.members.iter().filter(|(_, m)| m.id < id()).count()
+    }
+}
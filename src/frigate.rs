@@ -1,9 +1,32 @@
-use crate::pid::PID;
+use crate::commander::Commander;
+use crate::fire_discipline::{FireControl, FiringSolution};
+use crate::launch_envelope::{can_launch, Envelope};
+use crate::messages::Message;
+use crate::priority::{hit_probability, priority, PriorityWeights};
 use crate::radar_state::RadarState;
 use crate::target::Target;
-use crate::utils::turn_to_faster;
+use crate::threat::{assign_weapons, zone_station, WeaponSlot, Zone, Zones};
+use crate::utils::{
+    align, class_guns, plan_heading, send_class_and_position, threat_axis, thrust_toward,
+    time_to_align, Arena, FriendlyHulls, HeadingGoal, OrientationGoal, OrientationWeights,
+    TrajectoryGoal, BEACON_CHANNEL,
+};
 use maths_rs::num::Cast;
 use oort_api::prelude::*;
+const MAIN_GUN_RELOADING_WEIGHT: f64 = 0.2;
+/// Within this of the main gun's heading, track its moving goal rather than a fixed one.
+const MAIN_GUN_TRACKING_ANGLE: f64 = 0.1;
+/// Channel weapon 3's missiles listen on. Friendly fighters report their targets on it for
+/// their own missiles.
+const MISSILE_CHANNEL: usize = 0;
+/// A fighter's target report is followed for this long after it was heard.
+const FIGHTER_REPORT_TICKS: u32 = 10;
+/// A fighter's reported target within this of one of ours is taken to be the same ship.
+const TRACK_MATCH_DISTANCE: f64 = 300.0;
+/// How long to keep updating known targets before sweeping for new ones again.
+const RESCAN_TICKS: u32 = 300;
+/// Fraction of the main gun's reach to hold the main enemy capital ship at.
+const STANDOFF_FRACTION: f64 = 0.8;
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum FrigateRadarMode {
     FindNewTargets,
@@ -14,8 +37,16 @@ pub struct Frigate {
     index: usize,
     radar_mode: FrigateRadarMode,
     scan_radar: RadarState,
-    pid: PID,
     found_all_targets: bool,
+    /// How far the search radar has turned since it last found something new.
+    swept: f64,
+    last_search: u32,
+    station: Vec2,
+    fighter_report: Option<(Vec2, Vec2, u32)>,
+    last_sent: Option<Vec2>,
+    commander: Commander,
+    fire_control: FireControl,
+    hulls: FriendlyHulls,
 }
 impl Default for Frigate {
     fn default() -> Self {
@@ -30,47 +61,79 @@ impl Frigate {
             index: 0,
             radar_mode: FrigateRadarMode::FindNewTargets,
             scan_radar: RadarState::new(),
-            pid: PID::new(
-                12.0,
-                0.0,
-                6.0,
-                max_angular_acceleration(),
-                max_angular_acceleration(),
-            ),
             found_all_targets: false,
+            swept: 0.0,
+            last_search: 0,
+            station: position(),
+            fighter_report: None,
+            last_sent: None,
+            commander: Commander::new(),
+            fire_control: FireControl::new(),
+            hulls: FriendlyHulls::new(),
         }
     }
     pub fn tick(&mut self) {
+        // Radio 1 carries the hull beacon every other tick and fleet command traffic in the
+        // fleet's command slot, and listens on the beacon channel in between.
+        select_radio(1);
+        let msg = receive_bytes();
+        self.commander.listen(msg.as_ref());
+        if let Some(msg) = &msg {
+            self.hulls.hear_beacon(msg);
+        }
+        if current_tick().is_multiple_of(2) {
+            set_radio_channel(BEACON_CHANNEL);
+            send_class_and_position();
+        } else if Commander::is_command_tick() {
+            self.commander.transmit();
+        }
         debug!("targets {:?}", self.targets.len());
         debug!("index {:?}", self.index);
         debug!("radar_mode {:?}", self.radar_mode);
-        if reload_ticks(3) == 0 {
-            fire(3);
-        }
         if self.radar_mode == FrigateRadarMode::FindNewTargets {
             self.find_targets();
         } else if self.radar_mode == FrigateRadarMode::UpdateTargets {
             self.update_targets();
         }
-        self.fire_turrets();
+        let zones = Zones::for_class(class());
+        self.direct_missiles();
+        self.fire_turrets(&zones);
+        if let Some(station) =
+            zone_station(&self.targets, &zones, STANDOFF_FRACTION).or_else(|| {
+                self.commander
+                    .ordered_station(zones.medium * STANDOFF_FRACTION)
+            })
+        {
+            self.station = station;
+        }
+        thrust_toward(
+            &TrajectoryGoal::stop_at(Arena::current().clamp(self.station)),
+            &self.hulls,
+        );
         for (i, t) in self.targets.iter_mut().enumerate() {
             t.tick(i);
         }
     }
+    /// Sweeps for targets until the radar has gone all the way round without finding a
+    /// new one, however many there turn out to be.
     fn find_targets(&mut self) {
         if let Some(contact) = scan() {
-            self.new_target(contact.position, contact.velocity, contact.class);
+            if self.new_target(contact.position, contact.velocity, contact.class) {
+                self.swept = 0.0;
+            }
             set_radar_min_distance(contact.position.distance(position()) + 20.0);
         } else {
+            self.swept += radar_width();
             self.scan_radar.rotate();
         }
         self.scan_radar.save();
-        if self.targets.len() >= 5 {
+        if self.swept >= TAU && !self.targets.is_empty() {
             self.found_all_targets = true;
         }
         if self.found_all_targets {
             self.targets[0].load_radar();
             self.index = 0;
+            self.last_search = current_tick();
             self.radar_mode = FrigateRadarMode::UpdateTargets;
         } else {
             self.scan_radar.restore();
@@ -78,8 +141,8 @@ impl Frigate {
         }
     }
     fn update_targets(&mut self) {
-        if let Some(contact) = scan() {
-            if contact.class == Class::Missile || contact.class == Class::Torpedo {
+        let next = if let Some(contact) = scan() {
+            if is_ordnance(contact.class) && !is_ordnance(self.targets[self.index].class) {
                 if self.index + 1 < self.targets.len() {
                     self.index += 1;
                     self.targets[self.index].load_radar();
@@ -90,8 +153,8 @@ impl Frigate {
                 return;
             }
             let target = &mut self.targets[self.index];
-            if !target.sanity_check(contact.position, contact.velocity, contact.class) {
-                debug!("target failed sanity check");
+            if !target.consistent_with(contact.position, contact.velocity, contact.class) {
+                debug!("contact inconsistent with target");
                 let new_target = self
                     .targets
                     .iter_mut()
@@ -100,100 +163,165 @@ impl Frigate {
                 if let Some((i, new_target)) = new_target {
                     if i != self.index {
                         debug!("switching to new target");
-                        new_target.update(contact.position, contact.velocity);
+                        new_target.observe(&contact);
                     }
                 }
             } else {
-                target.update(contact.position, contact.velocity);
+                target.observe(&contact);
             }
+            self.fire_control
+                .observe(contact.class, contact.position, contact.velocity);
+            self.index + 1
         } else {
-            debug!("lost target");
+            let killed = self
+                .fire_control
+                .track_lost(self.targets[self.index].position);
+            debug!("lost target, killed by our guns: {}", killed);
             self.targets.remove(self.index);
-            self.index -= 1;
-        }
-        if self.index + 1 < self.targets.len() {
-            self.index += 1;
+            // The next target has moved into the slot just vacated.
+            self.index
+        };
+        if next < self.targets.len() {
+            self.index = next;
             self.targets[self.index].load_radar();
-        } else if !self.targets.is_empty() {
+        } else if !self.targets.is_empty() && current_tick() - self.last_search < RESCAN_TICKS {
             self.index = 0;
             self.targets[0].load_radar();
         } else {
             self.found_all_targets = false;
+            self.swept = 0.0;
             self.scan_radar.restore();
             self.radar_mode = FrigateRadarMode::FindNewTargets;
         }
     }
-    fn new_target(&mut self, new_position: Vec2, new_velocity: Vec2, new_class: Class) {
-        if new_class == Class::Missile || new_class == Class::Torpedo {
-            return;
+    /// Adds a target unless it is already known. Returns whether it was new.
+    fn new_target(&mut self, new_position: Vec2, new_velocity: Vec2, new_class: Class) -> bool {
+        // Ordnance is only worth tracking once the turrets can shoot it down.
+        if is_ordnance(new_class)
+            && position().distance(new_position) > Zones::for_class(class()).short
+        {
+            return false;
         }
         for t in &self.targets {
-            if t.sanity_check(new_position, new_velocity, new_class) {
-                return;
+            if t.consistent_with(new_position, new_velocity, new_class) {
+                return false;
             }
         }
         let t = Target::new(new_position, new_velocity, new_class);
         self.targets.push(t);
+        true
     }
-    fn fire_turrets(&mut self) {
-        if self.targets.is_empty() {
-            return;
+    /// Picks the target for weapon 3's missiles. When a fighter is reporting a target on
+    /// `MISSILE_CHANNEL` the frigate backs it up and stays off the air, so its missiles and
+    /// the fighters' converge on the same ship; otherwise it sends its own highest
+    /// priority ship.
+    fn direct_missiles(&mut self) {
+        select_radio(0);
+        set_radio_channel(MISSILE_CHANNEL);
+        let heard = Message::receive()
+            .and_then(|m| m.target())
+            .filter(|(p, _)| !self.last_sent.is_some_and(|s| s.distance(*p) < 1.0));
+        if let Some((position, velocity)) = heard {
+            self.fighter_report = Some((position, velocity, current_tick()));
         }
-        let min_shots = self
-            .targets
-            .iter()
-            .map(|t| t.shots_fired)
-            .min()
-            .unwrap_or(0);
-        let mut free_targets = self
-            .targets
-            .iter()
-            .enumerate()
-            .filter(|(_, t)| t.shots_fired == min_shots)
-            .map(|(i, _)| i)
-            .collect::<Vec<_>>();
-        for weapon_idx in [0, 1, 2, 3] {
-            let mut t_index = free_targets[weapon_idx % free_targets.len()];
-            if weapon_idx == 0 {
-                t_index = free_targets
+        let report = self
+            .fighter_report
+            .filter(|&(_, _, tick)| current_tick() - tick < FIGHTER_REPORT_TICKS);
+        let target = match report {
+            Some((reported, velocity, _)) => Some(
+                self.targets
                     .iter()
-                    .map(|&i| (i, angle_diff(heading(), self.targets[i].position.angle())))
-                    .min_by(|a, b| a.1.abs().partial_cmp(&b.1.abs()).unwrap())
-                    .unwrap()
-                    .0;
-                if free_targets.len() > 2 {
-                    free_targets.retain(|&x| x != t_index);
-                }
+                    .find(|t| t.position.distance(reported) < TRACK_MATCH_DISTANCE)
+                    .cloned()
+                    .unwrap_or_else(|| Target::new(reported, velocity, Class::Unknown)),
+            ),
+            None => {
+                let weights = PriorityWeights::current(self.commander.role());
+                let score = |t: &Target| priority(t, None, &weights);
+                self.targets
+                    .iter()
+                    .filter(|t| t.validation.is_confirmed() && !is_ordnance(t.class))
+                    .max_by(|a, b| score(a).partial_cmp(&score(b)).unwrap())
+                    .cloned()
             }
+        };
+        self.last_sent = None;
+        self.commander
+            .set_contact(target.as_ref().map(|t| t.position));
+        let Some(target) = target else {
+            return;
+        };
+        debug!(
+            "Missiles targeting {:?}, reloaded in {}",
+            target.class,
+            reload_ticks(3)
+        );
+        if report.is_none() {
+            Message::Target {
+                position: target.position,
+                velocity: target.velocity,
+            }
+            .send();
+            self.last_sent = Some(target.position);
+        }
+        if reload_ticks(3) == 0 && can_launch(Class::Missile, &[target], Envelope::Reachable) {
+            fire(3);
+        }
+    }
+    /// Points the main gun and turrets at targets by priority: the main gun at anything in
+    /// its reach, the turrets at whatever gets into point-defence range.
+    fn fire_turrets(&mut self, zones: &Zones) {
+        let guns = class_guns(Class::Frigate);
+        let weapons = [
+            WeaponSlot {
+                index: 0,
+                zones: vec![Zone::Short, Zone::Medium],
+                point_defence: false,
+                bullet_speed: Some(guns[0].bullet_speed),
+            },
+            WeaponSlot {
+                index: 1,
+                zones: vec![Zone::Short],
+                point_defence: true,
+                bullet_speed: Some(guns[1].bullet_speed),
+            },
+            WeaponSlot {
+                index: 2,
+                zones: vec![Zone::Short],
+                point_defence: true,
+                bullet_speed: Some(guns[2].bullet_speed),
+            },
+        ];
+        let weights = PriorityWeights::current(self.commander.role());
+        let mut main_goal = None;
+        let mut assignments = Vec::new();
+        for (weapon_idx, t_index) in assign_weapons(&self.targets, &weapons, zones, &weights) {
             let target = &mut self.targets[t_index];
+            let prediction =
+                target.lead(weapon_idx) + self.fire_control.lead_correction(target.position);
+            let angle = prediction.angle();
+            assignments.push((weapon_idx, target.position));
+            let bullet_speed = guns[weapon_idx].bullet_speed;
+            let mut solution = FiringSolution {
+                aim_angle: heading(),
+                target_class: target.class,
+                target_position: target.position,
+                target_velocity: target.velocity,
+                time_of_flight: prediction.length() / bullet_speed,
+                hit_probability: hit_probability(target, bullet_speed),
+            };
             if weapon_idx == 0 {
                 debug!(
                     "Main weapon targeting {}, reloded in {}",
                     t_index,
                     reload_ticks(weapon_idx)
                 );
-                let prediction = target.lead(weapon_idx);
-                let angle = prediction.angle();
                 let miss_by = angle_diff(heading(), angle) * prediction.length();
-                turn_to_faster(target);
+                main_goal = target.heading_goal();
                 debug!("Miss by {}", miss_by);
-                if miss_by.abs() < 6.0 && reload_ticks(weapon_idx) == 0 {
-                    fire(weapon_idx);
-                    self.pid.reset();
+                if miss_by.abs() < 6.0 && self.fire_control.fire_at(weapon_idx, &solution) {
                     target.shots_fired += 1;
                 }
-            } else if weapon_idx == 3 {
-                debug!(
-                    "Missiles targeting {}, reloaded in {}",
-                    t_index,
-                    reload_ticks(weapon_idx)
-                );
-                send([
-                    target.position.x,
-                    target.position.y,
-                    target.velocity.x,
-                    target.velocity.y,
-                ]);
             } else {
                 debug!(
                     "Turret {} targeting {}, reloded in {}",
@@ -201,11 +329,38 @@ impl Frigate {
                     t_index,
                     reload_ticks(weapon_idx)
                 );
-                let prediction = target.lead(weapon_idx);
-                let angle = prediction.angle();
-                aim(weapon_idx, angle);
-                fire(weapon_idx);
+                solution.aim_angle = angle + self.fire_control.dispersion(weapon_idx);
+                aim(weapon_idx, solution.aim_angle);
+                self.fire_control.fire_at(weapon_idx, &solution);
+            }
+        }
+        self.orient_hull(main_goal, assignments);
+    }
+    /// Turns the hull to the heading `plan_heading` picks. The main gun only dominates
+    /// when it will have reloaded by the time the hull could swing back onto its target;
+    /// until then the turrets and the hull's profile get more say.
+    fn orient_hull(&self, main_goal: Option<HeadingGoal>, assignments: Vec<(usize, Vec2)>) {
+        let mut weights = OrientationWeights::frigate();
+        if let Some(goal) = main_goal {
+            if reload_ticks(0) as f64 * TICK_LENGTH > time_to_align(&goal) {
+                weights.main_gun *= MAIN_GUN_RELOADING_WEIGHT;
             }
         }
+        let goal = OrientationGoal {
+            assignments,
+            threat_axis: threat_axis(&self.targets),
+            main_gun: main_goal.map(|g| g.heading),
+        };
+        let hull_heading = plan_heading(class(), &goal, &weights);
+        match main_goal {
+            Some(g) if angle_diff(hull_heading, g.heading).abs() < MAIN_GUN_TRACKING_ANGLE => {
+                align(&g)
+            }
+            _ => align(&HeadingGoal::fixed(hull_heading)),
+        }
     }
 }
+
+fn is_ordnance(class: Class) -> bool {
+    class == Class::Missile || class == Class::Torpedo
+}
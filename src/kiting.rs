@@ -0,0 +1,162 @@
+use crate::evasion::time_of_flight;
+use crate::opponent::OpponentProfile;
+use crate::utils::{class_guns, class_max_acceleration, hull_radius, GunInfo, TrajectoryGoal};
+use oort_api::prelude::*;
+
+const BEARING_SAMPLES: usize = 36;
+/// Keep this much further out than the enemy's effective range.
+const SAFETY_MARGIN: f64 = 200.0;
+/// Where in the band to sit, as a fraction of the way from its inner to its outer edge.
+const BAND_POSITION: f64 = 0.6;
+/// Below this speed a ship's velocity says nothing about which way it is facing.
+const MIN_HEADING_SPEED: f64 = 20.0;
+
+/// Distances at which we can hit a target and it can't reliably hit us back.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RangeBand {
+    pub inner: f64,
+    pub outer: f64,
+}
+
+impl RangeBand {
+    /// Distance to hold. When the enemy outranges us there is no safe band, and we close
+    /// to our own effective range anyway.
+    pub fn preferred(&self) -> f64 {
+        if self.inner >= self.outer {
+            self.outer
+        } else {
+            self.inner + (self.outer - self.inner) * BAND_POSITION
+        }
+    }
+}
+
+/// Longest distance at which `gun`'s bullets reach a `target_class` ship, moving at `dv`
+/// relative to the gun along the line between them, before they expire or before the
+/// target, dodging at `target_acceleration`, could move its hull clear of the aim point.
+pub fn effective_range(
+    gun: &GunInfo,
+    target_class: Class,
+    target_acceleration: f64,
+    dv: f64,
+) -> f64 {
+    let radius = hull_radius(target_class).unwrap_or(10.0);
+    let acceleration = target_acceleration.max(1e-3);
+    let max_flight_time = gun
+        .bullet_lifetime
+        .min((2.0 * radius / acceleration).sqrt());
+    let reaches = |range: f64| {
+        time_of_flight(vec2(range, 0.0), vec2(dv, 0.0), gun.bullet_speed)
+            .is_some_and(|t| t <= max_flight_time)
+    };
+    let (mut low, mut high) = (0.0, gun.range() * 2.0);
+    for _ in 0..30 {
+        let mid = (low + high) / 2.0;
+        if reaches(mid) {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
+
+/// Band to hold on a `their_class` ship closing on us at `closing_speed`: outside the
+/// furthest its guns are effective against us, inside the furthest ours are against it.
+/// With a profile of how it has been flying, ours are judged against the way it actually
+/// dodges rather than the hardest its class could.
+pub fn range_band(
+    their_class: Class,
+    closing_speed: f64,
+    profile: Option<&OpponentProfile>,
+) -> RangeBand {
+    let best = |guns: Vec<GunInfo>, target: Class, acceleration: f64, dv: f64| {
+        guns.iter()
+            .map(|g| effective_range(g, target, acceleration, dv))
+            .fold(0.0, f64::max)
+    };
+    let their_dodge = profile.map_or(class_max_acceleration(their_class), |p| {
+        p.dodge_acceleration()
+    });
+    RangeBand {
+        inner: best(
+            class_guns(their_class),
+            class(),
+            class_max_acceleration(class()),
+            -closing_speed,
+        ) + SAFETY_MARGIN,
+        outer: best(class_guns(class()), their_class, their_dodge, closing_speed),
+    }
+}
+
+/// Where to hold on a target so that we stay in our range band and, if it has any, in
+/// the arc where the fewest of its guns bear. Its heading is guessed from its velocity, or
+/// assumed to be toward us when it is barely moving.
+pub fn kite_station(
+    their_class: Class,
+    their_position: Vec2,
+    their_velocity: Vec2,
+    profile: Option<&OpponentProfile>,
+) -> TrajectoryGoal {
+    let dp = position() - their_position;
+    let closing_speed = -(velocity() - their_velocity).dot(dp.normalize());
+    let range = range_band(their_class, closing_speed, profile).preferred();
+    let their_heading = if their_velocity.length() > MIN_HEADING_SPEED {
+        their_velocity.angle()
+    } else {
+        dp.angle()
+    };
+    let guns = class_guns(their_class);
+    let guns_bearing = |bearing: f64| {
+        guns.iter()
+            .filter(|g| angle_diff(g.arc_center, bearing).abs() <= g.arc_half_width)
+            .count()
+    };
+    let current = angle_diff(their_heading, dp.angle());
+    let bearing = (0..BEARING_SAMPLES)
+        .map(|i| TAU * i as f64 / BEARING_SAMPLES as f64)
+        .chain([current])
+        .min_by(|&a, &b| {
+            (guns_bearing(a), angle_diff(current, a).abs())
+                .partial_cmp(&(guns_bearing(b), angle_diff(current, b).abs()))
+                .unwrap()
+        })
+        .unwrap_or(current);
+    let station = their_position + vec2(range, 0.0).rotate(their_heading + bearing);
+    TrajectoryGoal::rendezvous(station, their_velocity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gun() -> GunInfo {
+        GunInfo {
+            bullet_speed: 1000.0,
+            reload_ticks: 60,
+            bullet_lifetime: 2.0,
+            arc_center: 0.0,
+            arc_half_width: 0.0,
+        }
+    }
+
+    #[test]
+    fn target_that_cannot_dodge_is_limited_by_bullet_lifetime() {
+        let range = effective_range(&gun(), Class::Fighter, 0.0, 0.0);
+        assert!((range - gun().range()).abs() < 1.0, "{range}");
+    }
+
+    #[test]
+    fn dodging_target_is_limited_by_the_time_to_clear_its_hull() {
+        // A fighter's 20 m half extent at 160 m/s² is cleared in half a second.
+        let range = effective_range(&gun(), Class::Fighter, 160.0, 0.0);
+        assert!((range - 500.0).abs() < 1.0, "{range}");
+    }
+
+    #[test]
+    fn closing_target_is_hit_from_further_out_than_a_receding_one() {
+        let closing = effective_range(&gun(), Class::Fighter, 160.0, -200.0);
+        let receding = effective_range(&gun(), Class::Fighter, 160.0, 200.0);
+        assert!((closing - 600.0).abs() < 1.0, "{closing}");
+        assert!((receding - 400.0).abs() < 1.0, "{receding}");
+    }
+}
@@ -0,0 +1,193 @@
+use crate::target::Target;
+use crate::utils::{class_info, class_max_acceleration, BOOST_TICKS};
+use oort_api::prelude::*;
+
+const STEP: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Envelope {
+    OutOfRange,
+    /// The missile can reach where the target will be if it holds its velocity.
+    Reachable,
+    /// The missile can reach every point the target can get to, however it manoeuvres.
+    NoEscape,
+}
+
+/// Flight parameters of a missile class. Fuel is the delta-v available, boost is the
+/// extra forward acceleration from `Ability::Boost` and how long it lasts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MissileParams {
+    pub acceleration: f64,
+    pub boost_acceleration: f64,
+    pub boost_time: f64,
+    pub fuel: f64,
+    pub max_flight_time: f64,
+}
+
+impl MissileParams {
+    pub fn for_class(class: Class) -> MissileParams {
+        let info = class_info(class);
+        MissileParams {
+            acceleration: info.forward_acceleration,
+            boost_acceleration: info.boost_acceleration,
+            boost_time: BOOST_TICKS as f64 * TICK_LENGTH,
+            fuel: info.fuel,
+            max_flight_time: if class == Class::Torpedo { 60.0 } else { 20.0 },
+        }
+    }
+
+    /// Parameters of the missile currently running this code, with the fuel it has left.
+    pub fn current() -> MissileParams {
+        MissileParams {
+            acceleration: max_forward_acceleration(),
+            boost_acceleration: 0.0,
+            boost_time: 0.0,
+            fuel: fuel(),
+            ..MissileParams::for_class(class())
+        }
+    }
+
+    /// Earliest time at which a missile starting with relative offset `dp` and relative
+    /// velocity `dv` to the target can cover every point the target can reach while
+    /// accelerating at up to `target_acceleration`.
+    pub fn intercept_time(&self, dp: Vec2, dv: Vec2, target_acceleration: f64) -> Option<f64> {
+        self.intercept_times(dp, dv, target_acceleration).1
+    }
+
+    /// Earliest times at which the missile can meet the target if it holds its velocity,
+    /// and if it manoeuvres at up to `target_acceleration`, from one pass over the flight.
+    fn intercept_times(
+        &self,
+        dp: Vec2,
+        dv: Vec2,
+        target_acceleration: f64,
+    ) -> (Option<f64>, Option<f64>) {
+        let mut reachable = None;
+        let mut time = 0.0;
+        let mut speed = 0.0;
+        let mut reach = 0.0;
+        let mut fuel = self.fuel;
+        while time < self.max_flight_time {
+            time += STEP;
+            if fuel > 0.0 {
+                let boost = if time < self.boost_time {
+                    self.boost_acceleration
+                } else {
+                    0.0
+                };
+                let delta_v = ((self.acceleration + boost) * STEP).min(fuel);
+                fuel -= delta_v;
+                speed += delta_v;
+            }
+            reach += speed * STEP;
+            let distance = (dp + dv * time).length();
+            if reachable.is_none() && distance <= reach {
+                reachable = Some(time);
+            }
+            let escape = 0.5 * target_acceleration * time * time;
+            if distance + escape <= reach {
+                return (reachable, Some(time));
+            }
+        }
+        (reachable, None)
+    }
+
+    pub fn envelope(&self, target: &Target) -> Envelope {
+        let dp = target.position - position();
+        let dv = target.velocity - velocity();
+        match self.intercept_times(dp, dv, class_max_acceleration(target.class)) {
+            (_, Some(_)) => Envelope::NoEscape,
+            (Some(_), None) => Envelope::Reachable,
+            (None, None) => Envelope::OutOfRange,
+        }
+    }
+}
+
+/// Where `target` will be when the missile running this code, accelerating flat out,
+/// can meet it.
+pub fn intercept_point(target: &Target) -> Vec2 {
+    let dp = target.position - position();
+    let dv = target.velocity - velocity();
+    let params = MissileParams::current();
+    let time = params
+        .intercept_time(dp, dv, 0.0)
+        .unwrap_or(params.max_flight_time);
+    target.position + target.velocity * time
+}
+
+/// Whether a missile of `class` launched now can reach any of `targets` with at least the
+/// `required` envelope.
+pub fn can_launch(class: Class, targets: &[Target], required: Envelope) -> bool {
+    targets.iter().any(|t| match cached_envelope(class, t) {
+        Envelope::NoEscape => true,
+        Envelope::Reachable => required == Envelope::Reachable,
+        Envelope::OutOfRange => false,
+    })
+}
+
+/// Envelope of a `class` missile launched now against `target`, worked out at most once
+/// per target, class and tick.
+fn cached_envelope(class: Class, target: &Target) -> Envelope {
+    match target.envelope.get() {
+        Some((tick, cached_class, envelope)) if tick == current_tick() && cached_class == class => {
+            envelope
+        }
+        _ => {
+            let envelope = MissileParams::for_class(class).envelope(target);
+            target.envelope.set(Some((current_tick(), class, envelope)));
+            envelope
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fighter_at(position: Vec2, velocity: Vec2) -> Target {
+        Target::new(position, velocity, Class::Fighter)
+    }
+
+    #[test]
+    fn stationary_target_inside_the_envelope_cannot_escape() {
+        let params = MissileParams::for_class(Class::Missile);
+        let (reachable, no_escape) = params.intercept_times(
+            vec2(3000.0, 0.0),
+            vec2(0.0, 0.0),
+            class_max_acceleration(Class::Fighter),
+        );
+        let (reachable, no_escape) = (reachable.unwrap(), no_escape.unwrap());
+        assert!(reachable <= no_escape, "{reachable} > {no_escape}");
+        let target = fighter_at(vec2(3000.0, 0.0), vec2(0.0, 0.0));
+        assert!(can_launch(Class::Missile, &[target], Envelope::NoEscape));
+    }
+
+    #[test]
+    fn target_past_the_fuel_range_is_out_of_range() {
+        let params = MissileParams::for_class(Class::Missile);
+        let times = params.intercept_times(
+            vec2(50_000.0, 0.0),
+            vec2(0.0, 0.0),
+            class_max_acceleration(Class::Fighter),
+        );
+        assert_eq!(times, (None, None));
+        let target = fighter_at(vec2(50_000.0, 0.0), vec2(0.0, 0.0));
+        assert!(!can_launch(Class::Missile, &[target], Envelope::Reachable));
+    }
+
+    #[test]
+    fn target_that_can_outmanoeuvre_the_missile_is_only_reachable() {
+        let params = MissileParams::for_class(Class::Missile);
+        let (reachable, no_escape) = params.intercept_times(
+            vec2(10_000.0, 0.0),
+            vec2(500.0, 0.0),
+            class_max_acceleration(Class::Fighter),
+        );
+        assert!(reachable.is_some());
+        assert_eq!(no_escape, None);
+        let target = fighter_at(vec2(10_000.0, 0.0), vec2(500.0, 0.0));
+        let targets = [target];
+        assert!(!can_launch(Class::Missile, &targets, Envelope::NoEscape));
+        assert!(can_launch(Class::Missile, &targets, Envelope::Reachable));
+    }
+}
@@ -2,10 +2,26 @@ pub mod missiles;
 pub mod scenarios;
 pub mod cruiser;
 pub mod fighter;
+pub mod fire_discipline;
 pub mod frigate;
 pub mod pid;
+pub mod priority;
 pub mod radar_state;
 pub mod ship;
 pub mod target;
 pub mod utils;
+pub mod behaviour;
+pub mod commander;
+pub mod evasion;
+pub mod formation;
 pub mod kalman_filter;
+pub mod kiting;
+pub mod launch_envelope;
+pub mod messages;
+pub mod opponent;
+pub mod rounds;
+pub mod salvo;
+pub mod shot_prediction;
+pub mod threat;
+pub mod track_picture;
+pub mod validation;
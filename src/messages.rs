@@ -0,0 +1,471 @@
+use crate::commander::Role;
+use crate::formation::FormationShape;
+use crate::utils::{class_from_u8, VecUtils};
+use oort_api::prelude::*;
+
+const ORDER: u8 = 0x10;
+const IMPACT: u8 = 0x11;
+const KILL: u8 = 0x12;
+const TRACK: u8 = 0x13;
+const FORMATION: u8 = 0x14;
+const ROSTER: u8 = 0x15;
+const ROLE_ORDER: u8 = 0x16;
+const TARGET: u8 = 0x17;
+const LAUNCH: u8 = 0x18;
+/// Marks a launch slot no missile was fired from.
+const NO_CHANNEL: u8 = 0xff;
+/// A missile this close to the launcher's reported position when it spawned is one of its
+/// launches.
+const LAUNCH_MATCH_DISTANCE: f64 = 200.0;
+/// Ends every message. Read as the high half of an f64 it is a NaN, so it can't turn up in
+/// the last f64 of a raw `[x, y, vx, vy]` message or of a beacon's heading.
+const MAGIC: [u8; 4] = [0x17, 0x0a, 0xfc, 0x7f];
+const MAGIC_START: usize = 28;
+
+/// Tells every missile in a salvo what to hit, when, and from which side.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SalvoOrder {
+    pub group: u8,
+    pub size: u8,
+    /// Which of the salvo's approach bearings the missiles receiving this order take.
+    pub slot: u8,
+    pub impact_tick: u32,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub approach_bearing: f64,
+}
+
+/// Channels and salvo groups a launcher gave the missiles it fired on `tick`, with its pose
+/// then so each missile can tell which side of the hull it left from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LaunchOrder {
+    pub tick: u32,
+    pub position: Vec2,
+    pub heading: f64,
+    /// Channel and salvo group of the missile fired from each side, left then right. A
+    /// single missile gets the same assignment on both sides.
+    pub missiles: [Option<(u8, u8)>; 2],
+    /// Channel of the torpedo, if one was fired.
+    pub torpedo: Option<u8>,
+}
+
+impl LaunchOrder {
+    /// Channel and salvo group this order gives a `class` missile that spawned at
+    /// `spawn_position` on `spawn_tick`, if the order is about it. Torpedoes get group 0.
+    pub fn assignment(
+        &self,
+        class: Class,
+        spawn_tick: u32,
+        spawn_position: Vec2,
+    ) -> Option<(usize, u8)> {
+        if self.tick.abs_diff(spawn_tick) > 1
+            || self.position.distance(spawn_position) > LAUNCH_MATCH_DISTANCE
+        {
+            return None;
+        }
+        let (channel, group) = match class {
+            Class::Torpedo => (self.torpedo?, 0),
+            _ => {
+                let left = Vec2::angle_length(self.heading, 1.0)
+                    .wedge(spawn_position - self.position)
+                    >= 0.0;
+                self.missiles[if left { 0 } else { 1 }]?
+            }
+        };
+        Some((channel as usize, group))
+    }
+}
+
+/// What a formation member tells the others about itself each time it reports in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FormationReport {
+    pub id: u32,
+    pub shape: FormationShape,
+    pub engaged: bool,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub heading: f64,
+}
+
+/// A ship checking in with the fleet: who it is, what it is doing and what it can see.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RosterReport {
+    pub id: u32,
+    pub class: Class,
+    pub role: Role,
+    pub position: Vec2,
+    pub contact: Option<Vec2>,
+}
+
+/// The fleet commander's order to one ship: its role, the point to strike and the ship to
+/// guard.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoleOrder {
+    pub commander: u32,
+    pub ship: u32,
+    pub role: Role,
+    pub objective: Option<Vec2>,
+    pub guard: Option<Vec2>,
+}
+
+/// Byte-encoded radio messages shared between launchers and their missiles.
+/// Layout follows `send_class_and_position`: kind in byte 0, checksum in byte 7,
+/// payload packed as f32s from byte 8 onwards and `MAGIC` in the last four bytes.
+/// Anything else heard on the channel, such as beacons, decodes to `None`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Message {
+    Target {
+        position: Vec2,
+        velocity: Vec2,
+    },
+    Order(SalvoOrder),
+    Impact {
+        group: u8,
+        position: Vec2,
+    },
+    Kill {
+        position: Vec2,
+    },
+    Track {
+        class: Class,
+        position: Vec2,
+        velocity: Vec2,
+    },
+    Formation(FormationReport),
+    Roster(RosterReport),
+    RoleOrder(RoleOrder),
+    Launch(LaunchOrder),
+}
+
+impl Message {
+    pub fn encode(&self) -> [u8; 32] {
+        let mut msg = [0; 32];
+        match self {
+            Message::Target { position, velocity } => {
+                msg[0] = TARGET;
+                put_f32(&mut msg, 0, position.x);
+                put_f32(&mut msg, 1, position.y);
+                put_f32(&mut msg, 2, velocity.x);
+                put_f32(&mut msg, 3, velocity.y);
+            }
+            Message::Order(order) => {
+                msg[0] = ORDER;
+                msg[1] = order.group;
+                msg[2] = order.size & 0x0f | order.slot << 4;
+                msg[3..7].copy_from_slice(&order.impact_tick.to_le_bytes());
+                put_f32(&mut msg, 0, order.position.x);
+                put_f32(&mut msg, 1, order.position.y);
+                put_f32(&mut msg, 2, order.velocity.x);
+                put_f32(&mut msg, 3, order.velocity.y);
+                put_f32(&mut msg, 4, order.approach_bearing);
+            }
+            Message::Impact { group, position } => {
+                msg[0] = IMPACT;
+                msg[1] = *group;
+                put_f32(&mut msg, 0, position.x);
+                put_f32(&mut msg, 1, position.y);
+            }
+            Message::Kill { position } => {
+                msg[0] = KILL;
+                put_f32(&mut msg, 0, position.x);
+                put_f32(&mut msg, 1, position.y);
+            }
+            Message::Track {
+                class,
+                position,
+                velocity,
+            } => {
+                msg[0] = TRACK;
+                msg[1] = *class as u8;
+                put_f32(&mut msg, 0, position.x);
+                put_f32(&mut msg, 1, position.y);
+                put_f32(&mut msg, 2, velocity.x);
+                put_f32(&mut msg, 3, velocity.y);
+            }
+            Message::Formation(report) => {
+                msg[0] = FORMATION;
+                msg[1] = report.shape as u8;
+                msg[2] = report.engaged as u8;
+                msg[3..7].copy_from_slice(&report.id.to_le_bytes());
+                put_f32(&mut msg, 0, report.position.x);
+                put_f32(&mut msg, 1, report.position.y);
+                put_f32(&mut msg, 2, report.velocity.x);
+                put_f32(&mut msg, 3, report.velocity.y);
+                put_f32(&mut msg, 4, report.heading);
+            }
+            Message::Roster(report) => {
+                msg[0] = ROSTER;
+                msg[1] = report.class as u8;
+                msg[2] = report.role as u8;
+                msg[3..7].copy_from_slice(&report.id.to_le_bytes());
+                put_f32(&mut msg, 0, report.position.x);
+                put_f32(&mut msg, 1, report.position.y);
+                put_point(&mut msg, 2, report.contact);
+            }
+            Message::RoleOrder(order) => {
+                msg[0] = ROLE_ORDER;
+                msg[2] = order.role as u8;
+                msg[3..7].copy_from_slice(&order.ship.to_le_bytes());
+                msg[8..12].copy_from_slice(&order.commander.to_le_bytes());
+                put_point(&mut msg, 1, order.objective);
+                put_point(&mut msg, 3, order.guard);
+            }
+            Message::Launch(order) => {
+                msg[0] = LAUNCH;
+                for (i, missile) in order.missiles.iter().enumerate() {
+                    let (channel, group) = missile.unwrap_or((NO_CHANNEL, 0));
+                    msg[1 + 2 * i] = channel;
+                    msg[2 + 2 * i] = group;
+                }
+                msg[5] = order.torpedo.unwrap_or(NO_CHANNEL);
+                msg[8..12].copy_from_slice(&order.tick.to_le_bytes());
+                put_f32(&mut msg, 1, order.position.x);
+                put_f32(&mut msg, 2, order.position.y);
+                put_f32(&mut msg, 3, order.heading);
+            }
+        }
+        msg[MAGIC_START..].copy_from_slice(&MAGIC);
+        msg[7] = checksum(&msg);
+        msg
+    }
+
+    pub fn decode(msg: &[u8; 32]) -> Option<Message> {
+        if msg[MAGIC_START..] != MAGIC {
+            return None;
+        }
+        if checksum(msg) != msg[7] {
+            debug!("Checksum failed");
+            return None;
+        }
+        match msg[0] {
+            TARGET => Some(Message::Target {
+                position: vec2(get_f32(msg, 0), get_f32(msg, 1)),
+                velocity: vec2(get_f32(msg, 2), get_f32(msg, 3)),
+            }),
+            ORDER => Some(Message::Order(SalvoOrder {
+                group: msg[1],
+                size: msg[2] & 0x0f,
+                slot: msg[2] >> 4,
+                impact_tick: u32::from_le_bytes(msg[3..7].try_into().unwrap()),
+                position: vec2(get_f32(msg, 0), get_f32(msg, 1)),
+                velocity: vec2(get_f32(msg, 2), get_f32(msg, 3)),
+                approach_bearing: get_f32(msg, 4),
+            })),
+            IMPACT => Some(Message::Impact {
+                group: msg[1],
+                position: vec2(get_f32(msg, 0), get_f32(msg, 1)),
+            }),
+            KILL => Some(Message::Kill {
+                position: vec2(get_f32(msg, 0), get_f32(msg, 1)),
+            }),
+            TRACK => Some(Message::Track {
+                class: class_from_u8(msg[1]),
+                position: vec2(get_f32(msg, 0), get_f32(msg, 1)),
+                velocity: vec2(get_f32(msg, 2), get_f32(msg, 3)),
+            }),
+            FORMATION => Some(Message::Formation(FormationReport {
+                id: u32::from_le_bytes(msg[3..7].try_into().unwrap()),
+                shape: FormationShape::from_u8(msg[1]),
+                engaged: msg[2] != 0,
+                position: vec2(get_f32(msg, 0), get_f32(msg, 1)),
+                velocity: vec2(get_f32(msg, 2), get_f32(msg, 3)),
+                heading: get_f32(msg, 4),
+            })),
+            ROSTER => Some(Message::Roster(RosterReport {
+                id: u32::from_le_bytes(msg[3..7].try_into().unwrap()),
+                class: class_from_u8(msg[1]),
+                role: Role::from_u8(msg[2]),
+                position: vec2(get_f32(msg, 0), get_f32(msg, 1)),
+                contact: get_point(msg, 2),
+            })),
+            ROLE_ORDER => Some(Message::RoleOrder(RoleOrder {
+                commander: u32::from_le_bytes(msg[8..12].try_into().unwrap()),
+                ship: u32::from_le_bytes(msg[3..7].try_into().unwrap()),
+                role: Role::from_u8(msg[2]),
+                objective: get_point(msg, 1),
+                guard: get_point(msg, 3),
+            })),
+            LAUNCH => {
+                let missile = |i: usize| {
+                    (msg[1 + 2 * i] != NO_CHANNEL).then_some((msg[1 + 2 * i], msg[2 + 2 * i]))
+                };
+                Some(Message::Launch(LaunchOrder {
+                    tick: u32::from_le_bytes(msg[8..12].try_into().unwrap()),
+                    position: vec2(get_f32(msg, 1), get_f32(msg, 2)),
+                    heading: get_f32(msg, 3),
+                    missiles: [missile(0), missile(1)],
+                    torpedo: (msg[5] != NO_CHANNEL).then_some(msg[5]),
+                }))
+            }
+            _ => None,
+        }
+    }
+
+    /// Target position and velocity carried by the message, if any.
+    pub fn target(&self) -> Option<(Vec2, Vec2)> {
+        match *self {
+            Message::Target { position, velocity } => Some((position, velocity)),
+            Message::Order(order) => Some((order.position, order.velocity)),
+            _ => None,
+        }
+    }
+
+    pub fn send(&self) {
+        send_bytes(&self.encode());
+    }
+
+    pub fn receive() -> Option<Message> {
+        receive_bytes().and_then(|msg| Message::decode(&msg))
+    }
+}
+
+fn checksum(msg: &[u8; 32]) -> u8 {
+    msg.iter()
+        .enumerate()
+        .filter(|(i, _)| *i != 7)
+        .fold(0, |acc: u8, (_, x)| acc.wrapping_add(*x))
+}
+
+fn put_f32(msg: &mut [u8; 32], slot: usize, value: f64) {
+    let start = 8 + slot * 4;
+    msg[start..start + 4].copy_from_slice(&(value as f32).to_le_bytes());
+}
+
+fn get_f32(msg: &[u8; 32], slot: usize) -> f64 {
+    let start = 8 + slot * 4;
+    f32::from_le_bytes(msg[start..start + 4].try_into().unwrap()) as f64
+}
+
+/// Writes an optional point into two f32 slots, NaN standing for `None`.
+fn put_point(msg: &mut [u8; 32], slot: usize, point: Option<Vec2>) {
+    let point = point.unwrap_or(vec2(f64::NAN, f64::NAN));
+    put_f32(msg, slot, point.x);
+    put_f32(msg, slot + 1, point.y);
+}
+
+fn get_point(msg: &[u8; 32], slot: usize) -> Option<Vec2> {
+    let point = vec2(get_f32(msg, slot), get_f32(msg, slot + 1));
+    (!point.x.is_nan()).then_some(point)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(message: Message) {
+        assert_eq!(Message::decode(&message.encode()), Some(message));
+    }
+
+    #[test]
+    fn every_kind_round_trips() {
+        round_trip(Message::Target {
+            position: vec2(1500.0, -2500.5),
+            velocity: vec2(-120.25, 80.0),
+        });
+        round_trip(Message::Order(SalvoOrder {
+            group: 3,
+            size: 4,
+            slot: 2,
+            impact_tick: 12345,
+            position: vec2(100.0, 200.0),
+            velocity: vec2(-10.0, 5.5),
+            approach_bearing: 1.25,
+        }));
+        round_trip(Message::Impact {
+            group: 7,
+            position: vec2(-300.0, 400.0),
+        });
+        round_trip(Message::Kill {
+            position: vec2(0.5, -0.25),
+        });
+        round_trip(Message::Track {
+            class: Class::Frigate,
+            position: vec2(9000.0, -9000.0),
+            velocity: vec2(30.0, -40.0),
+        });
+        round_trip(Message::Formation(FormationReport {
+            id: 42,
+            shape: FormationShape::Wedge,
+            engaged: true,
+            position: vec2(10.0, 20.0),
+            velocity: vec2(1.0, 2.0),
+            heading: 0.75,
+        }));
+        round_trip(Message::Roster(RosterReport {
+            id: 9,
+            class: Class::Cruiser,
+            role: Role::PointDefence,
+            position: vec2(-1000.0, 1000.0),
+            contact: None,
+        }));
+        round_trip(Message::RoleOrder(RoleOrder {
+            commander: 1,
+            ship: 2,
+            role: Role::Strike,
+            objective: Some(vec2(5000.0, 0.0)),
+            guard: None,
+        }));
+        round_trip(Message::Launch(LaunchOrder {
+            tick: 4321,
+            position: vec2(-2000.0, 3000.0),
+            heading: 0.5,
+            missiles: [Some((2, 5)), None],
+            torpedo: Some(6),
+        }));
+    }
+
+    #[test]
+    fn missiles_pick_their_launch_assignment_by_side() {
+        let order = LaunchOrder {
+            tick: 100,
+            position: vec2(1000.0, 0.0),
+            heading: PI / 2.0,
+            missiles: [Some((1, 7)), Some((4, 7))],
+            torpedo: Some(3),
+        };
+        // Heading up the y axis, left is toward -x.
+        let left = vec2(970.0, 5.0);
+        let right = vec2(1030.0, 5.0);
+        assert_eq!(order.assignment(Class::Missile, 100, left), Some((1, 7)));
+        assert_eq!(order.assignment(Class::Missile, 101, right), Some((4, 7)));
+        assert_eq!(order.assignment(Class::Torpedo, 100, left), Some((3, 0)));
+        assert_eq!(order.assignment(Class::Missile, 110, left), None);
+        assert_eq!(order.assignment(Class::Missile, 100, vec2(0.0, 0.0)), None);
+    }
+
+    #[test]
+    fn raw_targets_are_not_messages() {
+        // x is chosen so its low byte is a message kind.
+        let x = f64::from_bits(0x40c3_8800_0000_0000 | ORDER as u64);
+        assert_eq!(x.to_bits() as u8, ORDER);
+        let mut msg = [0; 32];
+        for (i, value) in [x, 200.0, -30.0, 40.0].iter().enumerate() {
+            msg[i * 8..i * 8 + 8].copy_from_slice(&value.to_le_bytes());
+        }
+        msg[7] = checksum(&msg);
+        assert_eq!(Message::decode(&msg), None);
+    }
+
+    #[test]
+    fn beacons_are_not_messages() {
+        for heading in [0.0, 1.0, PI, TAU - 1e-9] {
+            let mut msg = [0; 32];
+            msg[0] = Class::Cruiser as u8;
+            msg[8..16].copy_from_slice(&1234.0f64.to_le_bytes());
+            msg[16..24].copy_from_slice(&(-5678.0f64).to_le_bytes());
+            msg[24..32].copy_from_slice(&f64::to_le_bytes(heading));
+            msg[7] = checksum(&msg);
+            assert_eq!(Message::decode(&msg), None);
+        }
+    }
+
+    #[test]
+    fn corrupted_messages_are_dropped() {
+        let mut msg = Message::Kill {
+            position: vec2(1.0, 2.0),
+        }
+        .encode();
+        msg[9] ^= 0x40;
+        assert_eq!(Message::decode(&msg), None);
+    }
+}
@@ -1,22 +1,44 @@
-use crate::missiles::Missile;
+use crate::launch_envelope::intercept_point;
+use crate::messages::{Message, SalvoOrder};
+use crate::missiles::{Launch, Missile};
+use crate::salvo::fly_salvo_leg;
 use crate::target::Target;
+use crate::track_picture::TrackPicture;
 use crate::utils::angle_at_distance;
 use crate::utils::final_approach;
-use crate::utils::{boost, seek, turn_to};
+use crate::utils::FriendlyHulls;
+use crate::utils::{boost, seek};
 use oort_api::prelude::*;
+/// Proximity fuse range.
+const DETONATION_RANGE: f64 = 130.0;
+/// A detonation is only reported as an impact when the target would have passed this close
+/// had the missile flown on, so the launcher doesn't confirm kills from near misses.
+const LETHAL_MISS: f64 = 50.0;
+/// Missile channels the cruiser orders salvos on.
+const MISSILE_CHANNELS: u32 = 8;
 pub struct CruiserMissile {
     target: Option<Target>,
     boost_time: Option<usize>,
     spawn_time: u32,
+    order: Option<SalvoOrder>,
+    final_leg: bool,
+    picture: TrackPicture,
+    hulls: FriendlyHulls,
+    launch: Launch,
 }
 impl Missile for CruiserMissile {
     fn new() -> CruiserMissile {
-        let radio_channel = id() % 4 + if position().y >= 0.0 { 0 } else { 4 };
-        set_radio_channel(radio_channel as usize);
+        // Only used should the launcher's launch order go unheard.
+        let fallback_channel = id() % MISSILE_CHANNELS;
         CruiserMissile {
+            hulls: FriendlyHulls::after_launch(fallback_channel as usize),
+            launch: Launch::new(),
             target: None,
             boost_time: None,
             spawn_time: current_tick(),
+            order: None,
+            final_leg: false,
+            picture: TrackPicture::new(),
         }
     }
     fn tick(&mut self) {
@@ -26,50 +48,38 @@ impl Missile for CruiserMissile {
         }
         debug!("id {:?}", id());
         debug!("radio_channel {:?}", get_radio_channel());
-        let (target_position, target_velocity) = if let Some(contact) = scan().filter(|c| {
-            ![Class::Missile, Class::Torpedo].contains(&c.class)
-                && !target_behind_cruiser(c.position)
-        }) {
-            (contact.position, contact.velocity)
-        } else if let Some(msg) = receive() {
-            (vec2(msg[0], msg[1]), vec2(msg[2], msg[3]))
-        } else if let Some(contact) =
+        let message = self.launch.receive(&mut self.hulls);
+        if self.picture.target_lost(message.as_ref(), &self.target) {
+            self.retarget();
+        }
+        let received = match message {
+            Some(Message::Order(order))
+                if self
+                    .launch
+                    .group
+                    .or(self.order.map(|o| o.group))
+                    .is_none_or(|g| g == order.group) =>
+            {
+                self.order = Some(order);
+                Some(order)
+            }
+            _ => None,
+        };
+        let (target_position, target_velocity) = if let Some(contact) =
             scan().filter(|c| ![Class::Missile, Class::Torpedo].contains(&c.class))
         {
             (contact.position, contact.velocity)
+        } else if let Some(order) = received {
+            (order.position, order.velocity)
+        } else if let Some(track) = self.picture.best_target() {
+            debug!("committing to {:?} from track picture", track.class);
+            (track.position, track.velocity)
         } else {
             no_target();
             self.target = None;
             debug!("no target");
             return;
         };
-        if class() != Class::Torpedo && target_behind_cruiser(target_position) {
-            debug!("target behind cruiser");
-            set_radar_heading((target_position - position()).angle());
-            set_radar_width(angle_at_distance(
-                position().distance(target_position),
-                100.0,
-            ));
-            set_radar_max_distance(position().distance(target_position) + 50.0);
-            set_radar_min_distance(position().distance(target_position) - 50.0);
-            let accel = if let Some(target) = &self.target {
-                draw_line(position(), target.position, 0xFF0000);
-                vec2(200.0, 0.0) * target.position.x.signum()
-                    + vec2(0.0, velocity().y.signum() * -max_lateral_acceleration())
-            } else {
-                self.target = Some(Target::new(
-                    target_position,
-                    target_velocity,
-                    Class::Missile,
-                ));
-                vec2(200.0, 0.0) * target_position.x.signum()
-                    + vec2(0.0, velocity().y.signum() * -max_lateral_acceleration())
-            };
-            accelerate(accel);
-            turn_to(accel.angle());
-            debug!("turn to {:>8.3}", accel.angle());
-            return;
-        }
         set_radar_heading((target_position - position()).angle());
         set_radar_width(angle_at_distance(
             position().distance(target_position),
@@ -80,7 +90,7 @@ impl Missile for CruiserMissile {
         if let Some(target) = self
             .target
             .as_mut()
-            .filter(|t| t.sanity_check(target_position, target_velocity, Class::Missile))
+            .filter(|t| t.consistent_with(target_position, target_velocity, Class::Missile))
         {
             target.update(target_position, target_velocity);
         } else {
@@ -92,6 +102,21 @@ impl Missile for CruiserMissile {
         }
         let target = self.target.as_ref().unwrap();
         let dp = target.position - position();
+        if self.hulls.steer_around(intercept_point(target)) {
+            return;
+        }
+        if let Some(order) = self.order.filter(|_| !self.final_leg) {
+            let order = SalvoOrder {
+                position: target.position,
+                velocity: target.velocity,
+                ..order
+            };
+            if fly_salvo_leg(&order) {
+                return;
+            }
+            debug!("salvo {} final leg", order.group);
+            self.final_leg = true;
+        }
         if dp.length() > 500.0 {
             seek(target);
         } else {
@@ -100,7 +125,17 @@ impl Missile for CruiserMissile {
         let dv = target.velocity - velocity();
         debug!("dp {:>8.3}", dp.length());
         debug!("dv {:>8.3}", dv.length());
-        if dp.length() < 130.0 {
+        if dp.length() < DETONATION_RANGE {
+            let time_to_closest = (-dp.dot(dv) / dv.dot(dv).max(1e-9)).max(0.0);
+            let miss = (dp + dv * time_to_closest).length();
+            debug!("detonating, closest approach {:.1}", miss);
+            if let Some(order) = self.order.filter(|_| miss < LETHAL_MISS) {
+                Message::Impact {
+                    group: order.group,
+                    position: target.position,
+                }
+                .send();
+            }
             explode();
         }
         let error = angle_diff(dp.angle(), heading()).abs();
@@ -109,10 +144,12 @@ impl Missile for CruiserMissile {
     }
 }
 
-fn target_behind_cruiser(target_position: Vec2) -> bool {
-    target_position.y.signum() != position().y.signum()
-        && target_position.y.abs() > 30.0
-        && position().x.abs() < 100.0
+impl CruiserMissile {
+    fn retarget(&mut self) {
+        self.target = None;
+        self.order = None;
+        self.final_leg = false;
+    }
 }
 
 fn no_target() {
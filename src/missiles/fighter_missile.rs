@@ -1,5 +1,7 @@
+use crate::messages::Message;
 use crate::missiles::Missile;
 use crate::target::Target;
+use crate::track_picture::TrackPicture;
 use crate::utils::angle_at_distance;
 use crate::utils::boost;
 use crate::utils::final_approach;
@@ -9,6 +11,7 @@ use oort_api::prelude::*;
 pub struct FighterMissile {
     target: Option<Target>,
     boost_time: Option<usize>,
+    picture: TrackPicture,
 }
 
 impl Missile for FighterMissile {
@@ -17,16 +20,24 @@ impl Missile for FighterMissile {
         FighterMissile {
             target: None,
             boost_time: None,
+            picture: TrackPicture::new(),
         }
     }
     fn tick(&mut self) {
+        let message = Message::receive();
+        if self.picture.target_lost(message.as_ref(), &self.target) {
+            self.target = None;
+        }
         let (target_position, target_velocity) = if let Some(contact) =
             scan().filter(|c| ![Class::Missile, Class::Torpedo].contains(&c.class))
         {
             debug!("contact {:?}", contact);
             (contact.position, contact.velocity)
-        } else if let Some(msg) = receive() {
-            (vec2(msg[0], msg[1]), vec2(msg[2], msg[3]))
+        } else if let Some(target) = message.and_then(|m| m.target()) {
+            target
+        } else if let Some(track) = self.picture.best_target() {
+            debug!("committing to {:?} from track picture", track.class);
+            (track.position, track.velocity)
         } else {
             set_radar_heading(radar_heading() + radar_width());
             set_radar_width(TAU / 4.0);
@@ -1,16 +1,21 @@
+use crate::launch_envelope::intercept_point;
+use crate::messages::Message;
 use crate::missiles::Missile;
 use crate::target::Target;
+use crate::track_picture::TrackPicture;
 use crate::utils::angle_at_distance;
 use crate::utils::boost;
 use crate::utils::boost_max_acceleration;
 use crate::utils::seek;
 use crate::utils::VecUtils;
-use crate::utils::{max_accelerate, turn_to};
+use crate::utils::{max_accelerate, turn_to, FriendlyHulls};
 use oort_api::prelude::*;
 
 pub struct FrigateMissile {
     target: Option<Target>,
     boost_time: Option<usize>,
+    picture: TrackPicture,
+    hulls: FriendlyHulls,
 }
 
 impl Missile for FrigateMissile {
@@ -19,17 +24,31 @@ impl Missile for FrigateMissile {
         FrigateMissile {
             target: None,
             boost_time: None,
+            picture: TrackPicture::new(),
+            hulls: FriendlyHulls::after_launch(0),
         }
     }
     fn tick(&mut self) {
+        let msg = receive_bytes();
+        let message = if self.hulls.listen(msg.as_ref()) {
+            None
+        } else {
+            msg.and_then(|msg| Message::decode(&msg))
+        };
+        if self.picture.target_lost(message.as_ref(), &self.target) {
+            self.target = None;
+        }
         let (target_position, target_velocity) = if let Some(contact) =
             scan().filter(|c| c.class != Class::Missile && self.target.is_some())
         {
             (contact.position, contact.velocity)
-        } else if let Some(msg) = receive() {
-            (vec2(msg[0], msg[1]), vec2(msg[2], msg[3]))
+        } else if let Some(target) = message.and_then(|m| m.target()) {
+            target
         } else if let Some(contact) = scan().filter(|c| c.class != Class::Missile) {
             (contact.position, contact.velocity)
+        } else if let Some(track) = self.picture.best_target() {
+            debug!("committing to {:?} from track picture", track.class);
+            (track.position, track.velocity)
         } else {
             set_radar_heading(radar_heading() + radar_width());
             set_radar_width(TAU / 4.0);
@@ -60,26 +79,8 @@ impl Missile for FrigateMissile {
                 Class::Missile,
             ));
         }
-        let behind = self.target_behind_frigate(target_position);
-        if behind {
-            debug!("Target behind frigate, evading");
-            let mut pos = vec2(200.0, 200.0);
-            let dp = target_position - position();
-            if dp.x.abs() < dp.y.abs() {
-                pos = vec2(
-                    pos.x * position().x.signum(),
-                    pos.y * target_position.y.signum(),
-                );
-            } else {
-                pos = vec2(
-                    pos.x * target_position.x.signum(),
-                    pos.y * position().y.signum(),
-                );
-            }
-            draw_line(position(), pos, 0xffff00);
-            turn_to((pos - position()).angle());
-            max_accelerate(pos - position());
-        } else {
+        let goal = intercept_point(self.target.as_ref().unwrap());
+        if !self.hulls.steer_around(goal) {
             self.seek_target();
         }
     }
@@ -103,11 +104,4 @@ impl FrigateMissile {
         let should_boost = error < PI / 4.0;
         boost(should_boost, &mut self.boost_time);
     }
-
-    fn target_behind_frigate(&self, target_position: Vec2) -> bool {
-        let target_angle = target_position.angle();
-        let missile_angle = position().angle();
-        let diff = angle_diff(target_angle, missile_angle);
-        PI - diff.abs() < PI / 6.0
-    }
 }
@@ -1,7 +1,55 @@
+use crate::messages::Message;
+use crate::utils::FriendlyHulls;
+use oort_api::prelude::*;
+
 pub mod cruiser_missile;
 pub mod fighter_missile;
 pub mod frigate_missile;
+pub mod torpedo;
 pub trait Missile {
     fn new() -> Self;
     fn tick(&mut self);
 }
+
+/// Where and when a missile spawned, to pick its launcher's `LaunchOrder` out of the
+/// beacon channel, and the salvo group the order gave it.
+pub struct Launch {
+    tick: u32,
+    position: Vec2,
+    pub group: Option<u8>,
+}
+
+impl Default for Launch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Launch {
+    pub fn new() -> Launch {
+        Launch {
+            tick: current_tick(),
+            position: position(),
+            group: None,
+        }
+    }
+
+    /// Reads the radio. While `hulls` holds it on the beacon channel, beacons are recorded
+    /// and a launch order for this missile hands the radio over to the channel it assigns.
+    /// Afterwards returns whatever was heard on that channel.
+    pub fn receive(&mut self, hulls: &mut FriendlyHulls) -> Option<Message> {
+        let msg = receive_bytes();
+        let message = msg.and_then(|msg| Message::decode(&msg));
+        if !hulls.listen(msg.as_ref()) {
+            return message;
+        }
+        if let Some(Message::Launch(order)) = message {
+            if let Some((channel, group)) = order.assignment(class(), self.tick, self.position) {
+                debug!("launched onto channel {} group {}", channel, group);
+                hulls.hand_over_to(channel);
+                self.group = Some(group);
+            }
+        }
+        None
+    }
+}
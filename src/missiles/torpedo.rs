@@ -0,0 +1,138 @@
+use crate::launch_envelope::intercept_point;
+use crate::missiles::{Launch, Missile};
+use crate::target::Target;
+use crate::track_picture::TrackPicture;
+use crate::utils::angle_at_distance;
+use crate::utils::hull_radius;
+use crate::utils::{max_accelerate, turn_to, FriendlyHulls, VecUtils};
+use oort_api::prelude::*;
+
+const CAPITAL_SHIPS: [Class; 2] = [Class::Frigate, Class::Cruiser];
+const TERMINAL_RANGE: f64 = 2000.0;
+const RADIO_FRESH_TICKS: u32 = 30;
+const LATERAL_GAIN: f64 = 2.0;
+/// Missile channels the cruiser broadcasts tracks on.
+const MISSILE_CHANNELS: u32 = 8;
+
+/// Guidance for slow, heavy torpedoes aimed at capital ships. Flies lead pursuit toward
+/// the intercept point its own acceleration allows, keeps the radar parked while the
+/// launcher's radio tracks are fresh, and switches to a terminal aim at the hull.
+pub struct Torpedo {
+    target: Option<Target>,
+    picture: TrackPicture,
+    /// When the launcher last sent a track on our target; None until it first has.
+    last_radio_tick: Option<u32>,
+    hulls: FriendlyHulls,
+    launch: Launch,
+}
+
+impl Missile for Torpedo {
+    fn new() -> Torpedo {
+        // Only used should the launcher's launch order go unheard.
+        let fallback_channel = id() % MISSILE_CHANNELS;
+        Torpedo {
+            hulls: FriendlyHulls::after_launch(fallback_channel as usize),
+            launch: Launch::new(),
+            target: None,
+            picture: TrackPicture::new(),
+            last_radio_tick: None,
+        }
+    }
+
+    fn tick(&mut self) {
+        let message = self.launch.receive(&mut self.hulls);
+        if self.picture.target_lost(message.as_ref(), &self.target) {
+            self.target = None;
+        }
+        if let Some(track) = self
+            .target
+            .as_ref()
+            .and_then(|t| self.picture.track_near(t.position))
+            .filter(|t| t.tick_updated == current_tick())
+        {
+            self.last_radio_tick = Some(current_tick());
+            self.update_target(track.position, track.velocity, track.class);
+        }
+        let silent = self.target.as_ref().is_some_and(|t| {
+            position().distance(t.position) > TERMINAL_RANGE
+                && self
+                    .last_radio_tick
+                    .is_some_and(|tick| current_tick() - tick < RADIO_FRESH_TICKS)
+        });
+        if silent {
+            park_radar();
+        } else if let Some(contact) = scan().filter(|c| CAPITAL_SHIPS.contains(&c.class)) {
+            self.update_target(contact.position, contact.velocity, contact.class);
+        }
+        if self.target.is_none() {
+            if let Some(track) = self.picture.best_target_of(&CAPITAL_SHIPS) {
+                debug!("committing to {:?} from track picture", track.class);
+                self.update_target(track.position, track.velocity, track.class);
+            } else {
+                set_radar_heading(radar_heading() + radar_width());
+                set_radar_width(TAU / 8.0);
+                set_radar_min_distance(0.0);
+                set_radar_max_distance(1e99);
+                max_accelerate(Vec2::angle_length(heading(), max_forward_acceleration()));
+                return;
+            }
+        }
+        let target = self.target.as_ref().unwrap();
+        let dp = target.position - position();
+        if !silent {
+            set_radar_heading(dp.angle());
+            set_radar_width(angle_at_distance(dp.length(), 200.0));
+            set_radar_min_distance(dp.length() - 200.0);
+            set_radar_max_distance(dp.length() + 200.0);
+        }
+        let aim_point = if dp.length() > TERMINAL_RANGE {
+            intercept_point(target)
+        } else {
+            hull_aim_point(target)
+        };
+        draw_line(position(), aim_point, 0xff8800);
+        steer_to(aim_point);
+        let hull_width = hull_radius(target.class).unwrap_or(50.0);
+        if dp.length() < hull_width + 20.0 {
+            explode();
+        }
+    }
+}
+
+impl Torpedo {
+    fn update_target(&mut self, position: Vec2, velocity: Vec2, class: Class) {
+        match &mut self.target {
+            Some(target) if target.position.distance(position) < 500.0 => {
+                target.update(position, velocity);
+            }
+            _ => self.target = Some(Target::new(position, velocity, class)),
+        }
+    }
+}
+
+/// Centre of the hull at the current time-to-go. Used inside terminal range, where the
+/// torpedo's remaining fuel no longer matters for the intercept.
+fn hull_aim_point(target: &Target) -> Vec2 {
+    let dp = target.position - position();
+    let closing_speed = -(target.velocity - velocity()).dot(dp.normalize());
+    let time_to_go = dp.length() / closing_speed.max(1.0);
+    target.position + target.velocity * time_to_go
+}
+
+/// Thrusts toward `aim_point` while cancelling the velocity that carries the torpedo
+/// sideways off the line to it.
+fn steer_to(aim_point: Vec2) {
+    let los = (aim_point - position()).normalize();
+    let lateral_velocity = velocity() - los * velocity().dot(los);
+    let command = los * max_forward_acceleration() - lateral_velocity * LATERAL_GAIN;
+    turn_to(command.angle());
+    max_accelerate(command);
+}
+
+/// Shrinks the radar's range to nothing so it picks up no returns while the launcher's
+/// tracks steer us. It only stops our own returns; the hull still shows up on enemy radar
+/// as before, so it doesn't make the torpedo any harder to detect.
+fn park_radar() {
+    set_radar_min_distance(0.0);
+    set_radar_max_distance(0.0);
+}
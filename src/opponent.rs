@@ -0,0 +1,181 @@
+use crate::utils::class_max_acceleration;
+use oort_api::prelude::*;
+
+/// Weight of the newest sample in the running averages.
+const SMOOTHING: f64 = 0.05;
+/// A contact this close to where a track was predicted to be is taken to be that track.
+const MATCH_DISTANCE: f64 = 200.0;
+const TRACK_TIMEOUT_TICKS: u32 = 600;
+/// How long after we fire a track counts as being under fire.
+const UNDER_FIRE_TICKS: u32 = 60;
+/// Below this speed the direction of travel is too noisy to give a turn rate.
+const MIN_TURN_SPEED: f64 = 20.0;
+/// Range rates below this count as holding range.
+const HOLDING_RANGE_RATE: f64 = 20.0;
+/// Samples needed before a profile is trusted over the class's book figures.
+const MIN_SAMPLES: u32 = 30;
+
+/// How an enemy has been seen to fly, as running averages over the battle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OpponentProfile {
+    pub samples: u32,
+    pub mean_acceleration: f64,
+    pub acceleration_deviation: f64,
+    pub max_acceleration: f64,
+    /// Rate its direction of travel changes, in radians per second.
+    pub turn_rate: f64,
+    /// Its mean acceleration while we are shooting at it over that while we are not.
+    pub fired_at_response: f64,
+    /// Distance it settles at when it holds range on us.
+    pub engagement_range: Option<f64>,
+}
+
+impl OpponentProfile {
+    fn new() -> OpponentProfile {
+        OpponentProfile {
+            samples: 0,
+            mean_acceleration: 0.0,
+            acceleration_deviation: 0.0,
+            max_acceleration: 0.0,
+            turn_rate: 0.0,
+            fired_at_response: 1.0,
+            engagement_range: None,
+        }
+    }
+
+    pub fn is_reliable(&self) -> bool {
+        self.samples >= MIN_SAMPLES
+    }
+
+    /// How far its current acceleration can be extrapolated, from 1 for a ship that
+    /// accelerates steadily to near 0 for one that jinks at random.
+    pub fn acceleration_trust(&self) -> f64 {
+        1.0 / (1.0 + self.acceleration_deviation / self.mean_acceleration.max(1.0))
+    }
+
+    /// Acceleration it can be expected to dodge with, for working out how long a shot
+    /// can be in flight before it is likely to miss.
+    pub fn dodge_acceleration(&self) -> f64 {
+        (self.mean_acceleration + self.acceleration_deviation).min(self.max_acceleration)
+    }
+
+    /// Time it needs to bring its nose round by `angle`, so to re-aim after we move.
+    pub fn reaction_time(&self, angle: f64) -> f64 {
+        angle / self.turn_rate.max(1e-3)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct OpponentTrack {
+    class: Class,
+    position: Vec2,
+    velocity: Vec2,
+    acceleration_under_fire: f64,
+    acceleration_calm: f64,
+    tick: u32,
+    profile: OpponentProfile,
+}
+
+impl OpponentTrack {
+    fn predicted_position(&self) -> Vec2 {
+        self.position + self.velocity * ((current_tick() - self.tick) as f64 * TICK_LENGTH)
+    }
+}
+
+/// Records how each enemy track manoeuvres so that lead, evasion and range keeping can
+/// plan against what it actually does rather than what its class could do.
+#[derive(Debug, Default)]
+pub struct OpponentModel {
+    tracks: Vec<OpponentTrack>,
+    last_shot: Option<u32>,
+}
+
+impl OpponentModel {
+    pub fn new() -> OpponentModel {
+        OpponentModel {
+            tracks: Vec::new(),
+            last_shot: None,
+        }
+    }
+
+    /// Tells the model we just fired at whatever we are observing.
+    pub fn shot_fired(&mut self) {
+        self.last_shot = Some(current_tick());
+    }
+
+    /// Folds a contact into the matching track's statistics.
+    pub fn observe(&mut self, class: Class, their_position: Vec2, their_velocity: Vec2) {
+        self.tracks
+            .retain(|t| current_tick() - t.tick < TRACK_TIMEOUT_TICKS);
+        let under_fire = self
+            .last_shot
+            .is_some_and(|tick| current_tick() - tick < UNDER_FIRE_TICKS);
+        let Some(track) = self.tracks.iter_mut().find(|t| {
+            t.class == class && t.predicted_position().distance(their_position) < MATCH_DISTANCE
+        }) else {
+            self.tracks.push(OpponentTrack {
+                class,
+                position: their_position,
+                velocity: their_velocity,
+                acceleration_under_fire: 0.0,
+                acceleration_calm: 0.0,
+                tick: current_tick(),
+                profile: OpponentProfile::new(),
+            });
+            return;
+        };
+        let dt = (current_tick() - track.tick).max(1) as f64 * TICK_LENGTH;
+        let acceleration = ((their_velocity - track.velocity) / dt).length();
+        // Readings past what the class can do are radar noise, not manoeuvres.
+        if acceleration <= class_max_acceleration(class) * 1.5 {
+            let p = &mut track.profile;
+            p.samples += 1;
+            let deviation = (acceleration - p.mean_acceleration).abs();
+            p.mean_acceleration = smooth(p.mean_acceleration, acceleration);
+            p.acceleration_deviation = smooth(p.acceleration_deviation, deviation);
+            p.max_acceleration = p.max_acceleration.max(acceleration);
+            if their_velocity.length() > MIN_TURN_SPEED && track.velocity.length() > MIN_TURN_SPEED
+            {
+                let turn = angle_diff(track.velocity.angle(), their_velocity.angle()).abs() / dt;
+                p.turn_rate = smooth(p.turn_rate, turn);
+            }
+            if under_fire {
+                track.acceleration_under_fire = smooth(track.acceleration_under_fire, acceleration);
+            } else {
+                track.acceleration_calm = smooth(track.acceleration_calm, acceleration);
+            }
+            if track.acceleration_calm > 0.0 && track.acceleration_under_fire > 0.0 {
+                p.fired_at_response = track.acceleration_under_fire / track.acceleration_calm;
+            }
+            let dp = their_position - position();
+            let range_rate = (their_velocity - velocity()).dot(dp) / dp.length();
+            if range_rate.abs() < HOLDING_RANGE_RATE {
+                p.engagement_range = Some(
+                    p.engagement_range
+                        .map_or(dp.length(), |r| smooth(r, dp.length())),
+                );
+            }
+        }
+        track.position = their_position;
+        track.velocity = their_velocity;
+        track.tick = current_tick();
+    }
+
+    /// Profile of the track nearest `position`, once it has enough samples to go on.
+    pub fn profile(&self, class: Class, position: Vec2) -> Option<OpponentProfile> {
+        self.tracks
+            .iter()
+            .filter(|t| t.class == class && t.profile.is_reliable())
+            .min_by(|a, b| {
+                a.predicted_position()
+                    .distance(position)
+                    .partial_cmp(&b.predicted_position().distance(position))
+                    .unwrap()
+            })
+            .map(|t| t.profile)
+    }
+}
+
+fn smooth(average: f64, sample: f64) -> f64 {
+    average + (sample - average) * SMOOTHING
+}
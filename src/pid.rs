@@ -1,4 +1,14 @@
-use oort_api::prelude::TICK_LENGTH;
+use crate::utils::class_info;
+use oort_api::prelude::*;
+
+/// Proportional, integral and derivative gains.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gains {
+    pub p: f64,
+    pub i: f64,
+    pub d: f64,
+}
+
 pub struct PID {
     pub p: f64,
     pub i: f64,
@@ -8,6 +18,19 @@ pub struct PID {
     pub integral_limit: f64,
     pub output_limit: f64,
     pub delta_time: f64,
+    /// Share of the setpoint the proportional term acts on. Below 1 it softens the kick
+    /// from a setpoint step without slowing disturbance rejection.
+    pub setpoint_weight: f64,
+    /// Share of the setpoint the derivative term acts on. 0 differentiates the measurement
+    /// only, so setpoint steps don't spike the output.
+    pub derivative_setpoint_weight: f64,
+    /// Caps the derivative's high-frequency gain at N times the proportional gain by
+    /// low-passing it with time constant `d / (p * N)`. `None` leaves it raw.
+    pub derivative_filter: Option<f64>,
+    /// Back-calculation gain: how fast the integral is bled off while the output is
+    /// saturated. 0 leaves only the `integral_limit` clamp.
+    pub tracking_gain: f64,
+    derivative: f64,
 }
 impl PID {
     pub fn new(p: f64, i: f64, d: f64, integral_limit: f64, output_limit: f64) -> PID {
@@ -20,26 +43,284 @@ impl PID {
             integral_limit,
             output_limit,
             delta_time: TICK_LENGTH,
+            setpoint_weight: 1.0,
+            derivative_setpoint_weight: 1.0,
+            derivative_filter: None,
+            tracking_gain: 0.0,
+            derivative: 0.0,
+        }
+    }
+
+    /// Heading controller for `class`, with the scheduled small-error gains, a filtered
+    /// derivative on measurement and back-calculation anti-windup.
+    pub fn for_class(class: Class) -> PID {
+        let limit = class_info(class).angular_acceleration;
+        let gains = GainSchedule::heading(class).gains_at(0.0);
+        PID {
+            derivative_setpoint_weight: 0.0,
+            derivative_filter: Some(10.0),
+            tracking_gain: 1.0,
+            ..PID::new(gains.p, gains.i, gains.d, limit, limit)
         }
     }
+
+    pub fn set_gains(&mut self, gains: Gains) {
+        self.p = gains.p;
+        self.i = gains.i;
+        self.d = gains.d;
+    }
+
+    /// Picks up the gains `schedule` gives for the current operating point.
+    pub fn schedule(&mut self, schedule: &GainSchedule, operating_point: f64) {
+        self.set_gains(schedule.gains_at(operating_point));
+    }
+
+    /// Update from the error alone, treated as a measurement against a zero setpoint so
+    /// the derivative still sees it when it acts on the measurement only.
     pub fn update(&mut self, error: f64) -> f64 {
-        let p = self.p * error;
+        self.update_with(0.0, -error)
+    }
+
+    /// Two-degree-of-freedom update from the setpoint and the measurement separately, so
+    /// the setpoint weights can apply.
+    pub fn update_with(&mut self, setpoint: f64, measurement: f64) -> f64 {
+        let error = setpoint - measurement;
+        let p = self.p * (self.setpoint_weight * setpoint - measurement);
         self.integral += error * self.delta_time;
         self.integral = self
             .integral
             .clamp(-self.integral_limit, self.integral_limit);
         let i = self.i * self.integral;
-        let derivative = match self.last_error {
-            Some(last_error) => (error - last_error) / self.delta_time,
+        let derivative_error = self.derivative_setpoint_weight * setpoint - measurement;
+        let raw_derivative = match self.last_error {
+            Some(last_error) => (derivative_error - last_error) / self.delta_time,
             None => 0.0,
         };
-        self.last_error = Some(error);
-        let d = self.d * derivative;
-        let output = p + i + d;
-        output.clamp(-self.output_limit, self.output_limit)
+        self.last_error = Some(derivative_error);
+        self.derivative = match self.derivative_filter {
+            Some(n) if self.p > 0.0 && self.d > 0.0 => {
+                let time_constant = self.d / (self.p * n);
+                let alpha = self.delta_time / (time_constant + self.delta_time);
+                self.derivative + alpha * (raw_derivative - self.derivative)
+            }
+            _ => raw_derivative,
+        };
+        let d = self.d * self.derivative;
+        let unsaturated = p + i + d;
+        let output = unsaturated.clamp(-self.output_limit, self.output_limit);
+        if self.i != 0.0 {
+            self.integral += self.tracking_gain * (output - unsaturated) / self.i * self.delta_time;
+        }
+        output
     }
     pub fn reset(&mut self) {
         self.last_error = None;
         self.integral = 0.0;
+        self.derivative = 0.0;
+    }
+}
+
+/// Gains tabulated against an operating point, interpolated linearly in between and held
+/// flat past either end.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GainSchedule {
+    points: Vec<(f64, Gains)>,
+}
+
+impl GainSchedule {
+    pub fn new(mut points: Vec<(f64, Gains)>) -> GainSchedule {
+        points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        GainSchedule { points }
+    }
+
+    /// Heading-hold gains for `class`, keyed by the size of the heading error. Large errors
+    /// saturate the torque anyway, so the proportional gain is eased off there to cut the
+    /// overshoot when the output comes back out of saturation.
+    pub fn heading(class: Class) -> GainSchedule {
+        let small = match class {
+            Class::Frigate => Gains {
+                p: 12.0,
+                i: 0.0,
+                d: 7.0,
+            },
+            Class::Cruiser => Gains {
+                p: 6.0,
+                i: 0.0,
+                d: 5.0,
+            },
+            _ => Gains {
+                p: 50.0,
+                i: 0.0,
+                d: 1000.0 / 60.0,
+            },
+        };
+        let large = Gains {
+            p: small.p / 2.0,
+            ..small
+        };
+        GainSchedule::new(vec![(0.0, small), (PI / 2.0, large)])
+    }
+
+    pub fn gains_at(&self, operating_point: f64) -> Gains {
+        let Some(&(first_point, first)) = self.points.first() else {
+            return Gains {
+                p: 0.0,
+                i: 0.0,
+                d: 0.0,
+            };
+        };
+        if operating_point <= first_point {
+            return first;
+        }
+        for window in self.points.windows(2) {
+            let (x0, g0) = window[0];
+            let (x1, g1) = window[1];
+            if operating_point <= x1 {
+                let t = (operating_point - x0) / (x1 - x0);
+                return Gains {
+                    p: g0.p + (g1.p - g0.p) * t,
+                    i: g0.i + (g1.i - g0.i) * t,
+                    d: g0.d + (g1.d - g0.d) * t,
+                };
+            }
+        }
+        self.points.last().unwrap().1
+    }
+}
+
+/// Relay-feedback autotuner. Drives the plant with a bang-bang output of `amplitude`
+/// around the setpoint, switching with some hysteresis, and measures the limit cycle that
+/// settles in. Its amplitude and period give the ultimate gain and period, and from those
+/// Ziegler-Nichols gains.
+pub struct RelayTuner {
+    amplitude: f64,
+    hysteresis: f64,
+    cycles: usize,
+    output: f64,
+    ticks: u32,
+    rising_switches: Vec<u32>,
+    peak: f64,
+    trough: f64,
+    last_swing: Option<f64>,
+}
+
+impl RelayTuner {
+    pub fn new(amplitude: f64, hysteresis: f64, cycles: usize) -> RelayTuner {
+        RelayTuner {
+            amplitude,
+            hysteresis,
+            cycles,
+            output: amplitude,
+            ticks: 0,
+            rising_switches: Vec::new(),
+            peak: f64::NEG_INFINITY,
+            trough: f64::INFINITY,
+            last_swing: None,
+        }
+    }
+
+    /// Relay output for this tick's error.
+    pub fn update(&mut self, error: f64) -> f64 {
+        self.ticks += 1;
+        self.peak = self.peak.max(error);
+        self.trough = self.trough.min(error);
+        if error > self.hysteresis && self.output < 0.0 {
+            self.output = self.amplitude;
+            if !self.rising_switches.is_empty() {
+                self.last_swing = Some((self.peak - self.trough) / 2.0);
+            }
+            self.rising_switches.push(self.ticks);
+            self.peak = error;
+            self.trough = error;
+        } else if error < -self.hysteresis && self.output > 0.0 {
+            self.output = -self.amplitude;
+        }
+        self.output
+    }
+
+    pub fn done(&self) -> bool {
+        self.rising_switches.len() > self.cycles
+    }
+
+    /// Ultimate gain and period, from the last complete cycle.
+    pub fn ultimate(&self) -> Option<(f64, f64)> {
+        let swing = self.last_swing?;
+        let n = self.rising_switches.len();
+        if n < 2 || swing <= self.hysteresis {
+            return None;
+        }
+        let period =
+            (self.rising_switches[n - 1] - self.rising_switches[n - 2]) as f64 * TICK_LENGTH;
+        let gain = 4.0 * self.amplitude
+            / (PI * (swing * swing - self.hysteresis * self.hysteresis).sqrt());
+        Some((gain, period))
+    }
+
+    /// Classic Ziegler-Nichols PID gains.
+    pub fn gains(&self) -> Option<Gains> {
+        let (ku, tu) = self.ultimate()?;
+        Some(Gains {
+            p: 0.6 * ku,
+            i: 1.2 * ku / tu,
+            d: 0.075 * ku * tu,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_TICKS: u32 = 1200;
+
+    /// Steps a torque-limited heading plant from rest at 0 toward `target` under the
+    /// class's scheduled heading controller. Returns the ticks it took to settle and the
+    /// furthest it swung past the target.
+    fn step_response(class: Class, target: f64) -> (u32, f64) {
+        let limit = class_info(class).angular_acceleration;
+        let schedule = GainSchedule::heading(class);
+        let mut pid = PID::for_class(class);
+        let (mut heading, mut angular_velocity): (f64, f64) = (0.0, 0.0);
+        let mut overshoot: f64 = 0.0;
+        for tick in 0..MAX_TICKS {
+            let error = angle_diff(heading, target);
+            if error.abs() < 1e-3 && angular_velocity.abs() < 1e-3 {
+                return (tick, overshoot);
+            }
+            pid.schedule(&schedule, error.abs());
+            let torque = pid.update(error).clamp(-limit, limit);
+            angular_velocity += torque * TICK_LENGTH;
+            heading += angular_velocity * TICK_LENGTH;
+            overshoot = overshoot.max(-angle_diff(heading, target) * target.signum());
+        }
+        (MAX_TICKS, overshoot)
+    }
+
+    #[test]
+    fn derivative_acts_on_plain_updates() {
+        let mut pid = PID::for_class(Class::Fighter);
+        pid.set_gains(Gains {
+            p: 0.0,
+            i: 0.0,
+            d: 1.0,
+        });
+        pid.derivative_filter = None;
+        pid.update(0.0);
+        let output = pid.update(0.01);
+        assert!((output - 0.01 / TICK_LENGTH).abs() < 1e-9, "got {output}");
+    }
+
+    #[test]
+    fn scheduled_heading_hold_settles() {
+        for class in [Class::Fighter, Class::Frigate, Class::Cruiser] {
+            for target in [PI / 2.0, -PI / 4.0, 0.1] {
+                let (ticks, overshoot) = step_response(class, target);
+                assert!(ticks < MAX_TICKS, "{class:?} to {target} never settles");
+                assert!(
+                    overshoot < 0.05 * target.abs(),
+                    "{class:?} to {target} overshoots by {overshoot}"
+                );
+            }
+        }
     }
 }
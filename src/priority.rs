@@ -0,0 +1,144 @@
+use crate::commander::Role;
+use crate::target::Target;
+use crate::threat::threat_score;
+use crate::utils::{class_max_acceleration, hull_radius};
+use oort_api::prelude::*;
+
+/// Distance at which the distance term halves a target's priority.
+const DISTANCE_SCALE: f64 = 5000.0;
+/// Radius assumed for contacts without a hull size, such as missiles and torpedoes.
+const ORDNANCE_RADIUS: f64 = 5.0;
+
+/// What killing each class is worth, before anything else is taken into account.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClassValues {
+    pub fighter: f64,
+    pub frigate: f64,
+    pub cruiser: f64,
+    pub missile: f64,
+    pub torpedo: f64,
+}
+
+impl ClassValues {
+    pub fn value(&self, class: Class) -> f64 {
+        match class {
+            Class::Fighter => self.fighter,
+            Class::Frigate => self.frigate,
+            Class::Cruiser => self.cruiser,
+            Class::Missile => self.missile,
+            Class::Torpedo => self.torpedo,
+            _ => 0.1,
+        }
+    }
+}
+
+/// How targets are ranked. Each weight is an exponent on its term, so 0 leaves the term
+/// out and larger values make it count for more.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriorityWeights {
+    pub class_values: ClassValues,
+    /// Favours whatever will reach us soonest, by `threat_score`.
+    pub threat: f64,
+    /// Favours nearer targets.
+    pub distance: f64,
+    /// Favours targets the weapon is likely to hit.
+    pub hit_probability: f64,
+    /// Favours targets that have taken less of our fire.
+    pub fire_taken: f64,
+}
+
+impl PriorityWeights {
+    /// Weights for a ship following `role`'s doctrine: point defence puts ordnance first,
+    /// strikers go for capital ships and screens for whatever is closing on the fleet.
+    pub fn for_role(role: Role) -> PriorityWeights {
+        match role {
+            Role::PointDefence => PriorityWeights {
+                class_values: ClassValues {
+                    fighter: 1.0,
+                    frigate: 2.0,
+                    cruiser: 3.0,
+                    missile: 4.0,
+                    torpedo: 6.0,
+                },
+                threat: 1.0,
+                distance: 0.5,
+                hit_probability: 1.0,
+                fire_taken: 0.5,
+            },
+            Role::Strike => PriorityWeights {
+                class_values: ClassValues {
+                    fighter: 1.0,
+                    frigate: 3.0,
+                    cruiser: 4.0,
+                    missile: 0.5,
+                    torpedo: 1.0,
+                },
+                threat: 0.5,
+                distance: 0.5,
+                hit_probability: 1.0,
+                fire_taken: 0.0,
+            },
+            Role::Screen => PriorityWeights {
+                class_values: ClassValues {
+                    fighter: 2.0,
+                    frigate: 2.0,
+                    cruiser: 2.0,
+                    missile: 3.0,
+                    torpedo: 4.0,
+                },
+                threat: 1.0,
+                distance: 1.0,
+                hit_probability: 1.0,
+                fire_taken: 1.0,
+            },
+        }
+    }
+
+    /// Adjusts the doctrine's weights for the scenario: in duels there is one ship to
+    /// kill, so fire is concentrated rather than spread.
+    pub fn for_scenario(self, scenario: &str) -> PriorityWeights {
+        match scenario {
+            "fighter_duel" | "frigate_duel" | "cruiser_duel" => PriorityWeights {
+                fire_taken: 0.0,
+                ..self
+            },
+            _ => self,
+        }
+    }
+
+    /// Weights for this ship in the current scenario under `role`.
+    pub fn current(role: Role) -> PriorityWeights {
+        PriorityWeights::for_role(role).for_scenario(scenario_name())
+    }
+}
+
+/// Chance a bullet at `bullet_speed` hits `target` if it dodges with its class's full
+/// acceleration for the time of flight.
+pub fn hit_probability(target: &Target, bullet_speed: f64) -> f64 {
+    let time_of_flight = position().distance(target.position) / bullet_speed;
+    dodge_hit_probability(
+        target.class,
+        time_of_flight,
+        class_max_acceleration(target.class),
+    )
+}
+
+/// Chance a bullet hits a `class` ship that dodges at `acceleration` for `time_of_flight`:
+/// its hull over the area it could be in by then.
+pub fn dodge_hit_probability(class: Class, time_of_flight: f64, acceleration: f64) -> f64 {
+    let radius = hull_radius(class).unwrap_or(ORDNANCE_RADIUS);
+    let dodge = acceleration * time_of_flight.powi(2) / 2.0;
+    (radius / dodge.max(radius)).powi(2)
+}
+
+/// How much `target` is worth shooting at with a weapon firing at `bullet_speed`, or with
+/// missiles when `None`, under `weights`.
+pub fn priority(target: &Target, bullet_speed: Option<f64>, weights: &PriorityWeights) -> f64 {
+    let distance = position().distance(target.position);
+    let hit = bullet_speed.map_or(1.0, |speed| hit_probability(target, speed));
+    weights.class_values.value(target.class)
+        * (1.0 + threat_score(target)).powf(weights.threat)
+        * hit.powf(weights.hit_probability)
+        / (1.0 + distance / DISTANCE_SCALE).powf(weights.distance)
+        / (1.0 + target.shots_fired as f64).powf(weights.fire_taken)
+}
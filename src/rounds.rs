@@ -0,0 +1,221 @@
+use crate::utils::{hull_radius, VecUtils};
+use oort_api::prelude::*;
+
+/// A round counts toward a track while the track is within this of where the round
+/// expected it to be.
+const TRACK_MATCH_DISTANCE: f64 = 100.0;
+/// Rounds not resolved this long after impact are dropped as unknown.
+const RESOLVE_TIMEOUT_TICKS: u32 = 30;
+/// A track not seen for this long loses its bias.
+const TRACK_TIMEOUT_TICKS: u32 = 600;
+/// Weight of the newest miss in a track's bias.
+const BIAS_SMOOTHING: f64 = 0.2;
+/// Largest lead correction applied, so one bad resolution can't throw the aim off.
+const MAX_CORRECTION: f64 = 50.0;
+/// Miss radius for contacts without a hull size, such as missiles and torpedoes.
+const ORDNANCE_RADIUS: f64 = 5.0;
+/// Resolved rounds a track's hit rate counts for as much as the modelled hit probability.
+const HIT_RATE_PRIOR: f64 = 10.0;
+
+/// A round we fired and the track it was fired at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Round {
+    pub weapon: usize,
+    pub origin: Vec2,
+    pub velocity: Vec2,
+    pub fired_tick: u32,
+    pub impact_tick: u32,
+    pub target_class: Class,
+    pub target_position: Vec2,
+    pub target_velocity: Vec2,
+    pub hit_probability: f64,
+}
+
+impl Round {
+    fn expected_target_position(&self) -> Vec2 {
+        let age = (current_tick() - self.fired_tick) as f64 * TICK_LENGTH;
+        self.target_position + self.target_velocity * age
+    }
+
+    fn position_at(&self, tick: u32) -> Vec2 {
+        self.origin + self.velocity * ((tick - self.fired_tick) as f64 * TICK_LENGTH)
+    }
+
+    /// Where the target was relative to the round as it passed, across the round's path.
+    /// Along the path the round keeps going, so only the sideways error counts.
+    fn miss(&self, target_at_impact: Vec2) -> Vec2 {
+        let error = target_at_impact - self.position_at(self.impact_tick);
+        let along = self.velocity.normalize();
+        error - along * error.dot(along)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Hit,
+    Miss,
+}
+
+/// What the rounds fired at one track have done, and the lead correction they add up to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TrackRecord {
+    class: Class,
+    position: Vec2,
+    velocity: Vec2,
+    tick: u32,
+    bias: Vec2,
+    hits: u32,
+    misses: u32,
+}
+
+impl TrackRecord {
+    fn predicted_position(&self) -> Vec2 {
+        self.position + self.velocity * ((current_tick() - self.tick) as f64 * TICK_LENGTH)
+    }
+}
+
+/// Every round we have in flight. When a track is next seen after a round's impact tick,
+/// the round is resolved as a hit or miss from where the track was as it passed, and the
+/// miss goes into the track's bias, which is fed back into the lead on it. A track lost
+/// right after rounds reached it counts them as hits.
+#[derive(Debug, Default)]
+pub struct RoundTracker {
+    rounds: Vec<Round>,
+    tracks: Vec<TrackRecord>,
+}
+
+impl RoundTracker {
+    pub fn new() -> RoundTracker {
+        RoundTracker {
+            rounds: Vec::new(),
+            tracks: Vec::new(),
+        }
+    }
+
+    pub fn fired(&mut self, round: Round) {
+        self.rounds.push(round);
+    }
+
+    /// Hits expected from rounds still on their way to the track now at `target_position`.
+    pub fn expected_hits(&self, target_position: Vec2) -> f64 {
+        self.rounds
+            .iter()
+            .filter(|r| {
+                r.impact_tick >= current_tick()
+                    && r.expected_target_position().distance(target_position) < TRACK_MATCH_DISTANCE
+            })
+            .map(|r| r.hit_probability)
+            .sum()
+    }
+
+    /// Resolves the rounds that have reached the track seen at `their_position`.
+    pub fn observe(&mut self, class: Class, their_position: Vec2, their_velocity: Vec2) {
+        self.rounds
+            .retain(|r| current_tick().saturating_sub(r.impact_tick) < RESOLVE_TIMEOUT_TICKS);
+        self.tracks
+            .retain(|t| current_tick() - t.tick < TRACK_TIMEOUT_TICKS);
+        let radius = hull_radius(class).unwrap_or(ORDNANCE_RADIUS);
+        let (resolved, pending): (Vec<Round>, Vec<Round>) = self.rounds.iter().partition(|r| {
+            r.target_class == class
+                && r.impact_tick <= current_tick()
+                && r.expected_target_position().distance(their_position) < TRACK_MATCH_DISTANCE
+        });
+        self.rounds = pending;
+        let track = self.track(class, their_position, their_velocity);
+        for round in resolved {
+            let since_impact = (current_tick() - round.impact_tick) as f64 * TICK_LENGTH;
+            let miss = round.miss(their_position - their_velocity * since_impact);
+            let outcome = if miss.length() < radius {
+                track.hits += 1;
+                Outcome::Hit
+            } else {
+                track.misses += 1;
+                Outcome::Miss
+            };
+            debug!(
+                "round from gun {} {:?} by {:.1}",
+                round.weapon,
+                outcome,
+                miss.length()
+            );
+            track.bias += (miss - track.bias) * BIAS_SMOOTHING;
+        }
+    }
+
+    /// Forgets the track lost at `last_position`. Returns whether rounds had reached it,
+    /// in which case they are taken to have killed it.
+    pub fn track_lost(&mut self, last_position: Vec2) -> bool {
+        let (hits, pending): (Vec<Round>, Vec<Round>) = self.rounds.iter().partition(|r| {
+            r.impact_tick <= current_tick()
+                && r.expected_target_position().distance(last_position) < TRACK_MATCH_DISTANCE
+        });
+        self.rounds = pending;
+        self.tracks
+            .retain(|t| t.predicted_position().distance(last_position) >= TRACK_MATCH_DISTANCE);
+        if !hits.is_empty() {
+            debug!("track killed by {} rounds", hits.len());
+        }
+        !hits.is_empty()
+    }
+
+    /// Offset to add to the lead on the track at `their_position`, from where our rounds
+    /// have been passing it.
+    pub fn correction(&self, their_position: Vec2) -> Vec2 {
+        self.find(their_position).map_or(Vec2::zero(), |t| {
+            let length = t.bias.length();
+            if length > MAX_CORRECTION {
+                t.bias * (MAX_CORRECTION / length)
+            } else {
+                t.bias
+            }
+        })
+    }
+
+    /// Chance of a hit on the track at `their_position`: `modelled` moved toward the
+    /// fraction of resolved rounds that hit it as they build up.
+    pub fn hit_probability(&self, their_position: Vec2, modelled: f64) -> f64 {
+        self.find(their_position).map_or(modelled, |t| {
+            (t.hits as f64 + modelled * HIT_RATE_PRIOR)
+                / ((t.hits + t.misses) as f64 + HIT_RATE_PRIOR)
+        })
+    }
+
+    fn find(&self, their_position: Vec2) -> Option<&TrackRecord> {
+        self.tracks
+            .iter()
+            .find(|t| t.predicted_position().distance(their_position) < TRACK_MATCH_DISTANCE)
+    }
+
+    /// The record for the track, started fresh if it hasn't been seen before.
+    fn track(
+        &mut self,
+        class: Class,
+        their_position: Vec2,
+        their_velocity: Vec2,
+    ) -> &mut TrackRecord {
+        let index = self
+            .tracks
+            .iter()
+            .position(|t| {
+                t.class == class
+                    && t.predicted_position().distance(their_position) < TRACK_MATCH_DISTANCE
+            })
+            .unwrap_or_else(|| {
+                self.tracks.push(TrackRecord {
+                    class,
+                    position: their_position,
+                    velocity: their_velocity,
+                    tick: current_tick(),
+                    bias: Vec2::zero(),
+                    hits: 0,
+                    misses: 0,
+                });
+                self.tracks.len() - 1
+            });
+        let track = &mut self.tracks[index];
+        track.position = their_position;
+        track.velocity = their_velocity;
+        track.tick = current_tick();
+        track
+    }
+}
@@ -0,0 +1,204 @@
+use crate::launch_envelope::MissileParams;
+use crate::messages::SalvoOrder;
+use crate::target::Target;
+use crate::utils::{max_accelerate, turn_to, VecUtils};
+use oort_api::prelude::*;
+
+const FLIGHT_TIME_SLACK: f64 = 1.3;
+const SALVO_SPREAD: f64 = PI / 6.0;
+const APPROACH_STANDOFF: f64 = 1000.0;
+const WAYPOINT_RADIUS: f64 = 150.0;
+const SALVO_MATCH_DISTANCE: f64 = 500.0;
+const SALVO_TIMEOUT: u32 = 300;
+const KILL_CONFIRM_TICKS: u32 = 120;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Salvo {
+    pub group: u8,
+    pub target_position: Vec2,
+    pub impact_tick: u32,
+    pub approach_bearing: f64,
+    pub impacts: u32,
+    pub last_impact_tick: Option<u32>,
+    pub confirmed: bool,
+}
+
+/// Launcher side of salvo coordination. Groups missiles by target, picks a shared impact
+/// tick and approach bearing, and confirms kills from the missiles' impact reports.
+#[derive(Debug)]
+pub struct SalvoPlanner {
+    salvos: Vec<Salvo>,
+    next_group: u8,
+}
+impl Default for SalvoPlanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SalvoPlanner {
+    pub fn new() -> SalvoPlanner {
+        SalvoPlanner {
+            salvos: Vec::new(),
+            next_group: 0,
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.salvos
+            .retain(|s| current_tick() < s.impact_tick + SALVO_TIMEOUT);
+        for s in &self.salvos {
+            draw_text!(
+                s.target_position + vec2(0.0, 60.0),
+                0xffff00,
+                "salvo {} t-{}",
+                s.group,
+                s.impact_tick.saturating_sub(current_tick())
+            );
+        }
+    }
+
+    /// Group for missiles launched at `target`: the open salvo close enough to join, or a
+    /// new one.
+    pub fn assign(&mut self, target: &Target) -> u8 {
+        let index = match self.find(target.position) {
+            Some(i) => i,
+            None => self.open(target),
+        };
+        self.salvos[index].group
+    }
+
+    /// Returns the order for salvo `group`, following its target through `targets`. `slot`
+    /// picks the approach bearing of the `size` missiles in flight in the salvo. `None`
+    /// once the salvo has timed out, its kill is confirmed or its target is lost.
+    pub fn order(
+        &mut self,
+        group: u8,
+        targets: &[Target],
+        size: u8,
+        slot: u8,
+    ) -> Option<SalvoOrder> {
+        let salvo = self
+            .salvos
+            .iter_mut()
+            .find(|s| s.group == group && !s.confirmed)?;
+        let target = targets
+            .iter()
+            .filter(|t| t.position.distance(salvo.target_position) < SALVO_MATCH_DISTANCE)
+            .min_by(|a, b| {
+                a.position
+                    .distance(salvo.target_position)
+                    .partial_cmp(&b.position.distance(salvo.target_position))
+                    .unwrap()
+            })?;
+        salvo.target_position = target.position;
+        Some(SalvoOrder {
+            group: salvo.group,
+            size,
+            slot,
+            impact_tick: salvo.impact_tick,
+            position: target.position,
+            velocity: target.velocity,
+            approach_bearing: salvo.approach_bearing,
+        })
+    }
+
+    pub fn record_impact(&mut self, group: u8) {
+        if let Some(salvo) = self.salvos.iter_mut().find(|s| s.group == group) {
+            debug!("salvo {} impact reported", group);
+            salvo.impacts += 1;
+            salvo.last_impact_tick = Some(current_tick());
+        }
+    }
+
+    /// Called when the radar loses a track. If a missile of the salvo on that track
+    /// reported an impact shortly before, or `killed` says something else got it, the
+    /// kill is confirmed and the salvo's group returned so its missiles can be released.
+    pub fn track_lost(&mut self, position: Vec2, killed: bool) -> Option<u8> {
+        let index = self.find(position)?;
+        let salvo = &mut self.salvos[index];
+        let recent = salvo
+            .last_impact_tick
+            .is_some_and(|t| current_tick() - t < KILL_CONFIRM_TICKS);
+        if recent || killed {
+            debug!("salvo {} confirmed kill", salvo.group);
+            salvo.confirmed = true;
+            Some(salvo.group)
+        } else {
+            None
+        }
+    }
+
+    fn find(&self, position: Vec2) -> Option<usize> {
+        self.salvos
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| !s.confirmed && s.impact_tick > current_tick())
+            .map(|(i, s)| (i, s.target_position.distance(position)))
+            .filter(|(_, d)| *d < SALVO_MATCH_DISTANCE)
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(i, _)| i)
+    }
+
+    fn open(&mut self, target: &Target) -> usize {
+        let params = MissileParams::for_class(Class::Missile);
+        let dp = target.position - position();
+        let dogleg = Vec2::angle_length(dp.angle(), APPROACH_STANDOFF);
+        let flight_time = params
+            .intercept_time(dp + dogleg, target.velocity - velocity(), 0.0)
+            .unwrap_or(params.max_flight_time)
+            * FLIGHT_TIME_SLACK;
+        let salvo = Salvo {
+            group: self.next_group,
+            target_position: target.position,
+            impact_tick: current_tick() + 1 + (flight_time / TICK_LENGTH).ceil() as u32,
+            approach_bearing: position().angle_to(target.position) + PI,
+            impacts: 0,
+            last_impact_tick: None,
+            confirmed: false,
+        };
+        debug!(
+            "opening salvo {} impact at {}",
+            salvo.group, salvo.impact_tick
+        );
+        self.next_group = self.next_group.wrapping_add(1);
+        self.salvos.push(salvo);
+        self.salvos.len() - 1
+    }
+}
+
+/// Missile side of salvo coordination. Flies a dogleg through the ordered slot's approach
+/// waypoint, throttling to the speed that arrives at the ordered impact tick. Returns
+/// `false` once the missile is on its final leg and should switch to terminal guidance.
+pub fn fly_salvo_leg(order: &SalvoOrder) -> bool {
+    let ticks_left = order.impact_tick.saturating_sub(current_tick());
+    if ticks_left == 0 {
+        return false;
+    }
+    let time_left = ticks_left as f64 * TICK_LENGTH;
+    let aim_point = order.position + order.velocity * time_left;
+    if position().distance(aim_point) < APPROACH_STANDOFF {
+        return false;
+    }
+    let size = order.size.max(1);
+    let offset = (order.slot.min(size - 1) as f64 - (size - 1) as f64 / 2.0) * SALVO_SPREAD;
+    let waypoint =
+        aim_point + Vec2::angle_length(order.approach_bearing + offset, APPROACH_STANDOFF);
+    let to_waypoint = waypoint - position();
+    if to_waypoint.length() < WAYPOINT_RADIUS {
+        return false;
+    }
+    let required_speed = (to_waypoint.length() + APPROACH_STANDOFF) / time_left;
+    let desired_velocity = Vec2::angle_length(to_waypoint.angle(), required_speed);
+    let dv = desired_velocity - velocity();
+    draw_line(position(), waypoint, 0xffff00);
+    draw_line(waypoint, aim_point, 0xffff00);
+    let heading_goal = if dv.length() > 1.0 {
+        dv.angle()
+    } else {
+        to_waypoint.angle()
+    };
+    turn_to(heading_goal);
+    max_accelerate(dv / TICK_LENGTH);
+    true
+}
@@ -1,7 +1,7 @@
 use oort_api::prelude::*;
 
 use crate::radar_state::RadarState;
-use crate::utils::{angle_at_distance, turn_to, turn_to_fast, VecUtils};
+use crate::utils::{align, angle_at_distance, time_to_align, turn_to, HeadingGoal, VecUtils};
 #[derive(Debug, Clone, PartialEq)]
 pub struct TargetState {
     position: Vec2,
@@ -12,6 +12,14 @@ pub struct TargetState {
     observations: usize,
 }
 impl TargetState {
+    /// Heading to the predicted position, moving at the rate it changed since last tick.
+    fn heading_goal(&mut self) -> Option<HeadingGoal> {
+        let target_heading = (self.predicted_position? - position()).angle();
+        let last_heading = self.last_heading.unwrap_or(target_heading);
+        self.last_heading = Some(target_heading);
+        let rate = angle_diff(last_heading, target_heading) / TICK_LENGTH;
+        Some(HeadingGoal::moving(target_heading, rate, 0.0))
+    }
     fn load_radar(&self) {
         let dp = self.position - position() + self.velocity * TICK_LENGTH;
         set_radar_heading(dp.angle());
@@ -201,7 +209,7 @@ impl Ship {
                 position() + Vec2::angle_length(heading(), fp.length()),
                 0x00ff00,
             );
-            turn_to_fast((fp).angle() - 0.0005);
+            turn_to((fp).angle() - 0.0005);
             if angle_diff((fp).angle(), heading()).abs() < 0.001 && reload_ticks(0) == 0 {
                 fire(0);
                 self.targets[0].shots_fired += 1;
@@ -236,7 +244,9 @@ impl Ship {
         let (target_heading, future_position) =
             lead_target(target.position, target.velocity, 4000.0);
         target.predicted_position = Some(future_position + position());
-        turn_to_target(target);
+        if let Some(goal) = target.heading_goal() {
+            align(&goal);
+        }
         let error = angle_diff(target_heading, heading());
         let miss_by = 2.0 * future_position.length() * error.sin();
         if miss_by.abs() < 20.0 && reload_ticks(0) == 0 {
@@ -288,7 +298,7 @@ impl Ship {
         let time_to_target = dp.length() / 4000.0;
         let mut future_position = dp + dv * time_to_target;
         for _ in 0..200 {
-            let turn_time = time_to_turn_to(future_position.angle());
+            let turn_time = time_to_align(&HeadingGoal::fixed(future_position.angle()));
             let time_to_target = (future_position.length() - 40.0) / 4000.0;
             let new_future_position = dp + dv * (time_to_target + turn_time);
             let delta = new_future_position.distance(future_position);
@@ -300,49 +310,6 @@ impl Ship {
         future_position
     }
 }
-fn turn_to_target(target: &mut TargetState) {
-    if target.predicted_position.is_none() {
-        return;
-    }
-    let target_heading = (target.predicted_position.unwrap() - position()).angle();
-    let last_heading = target.last_heading.unwrap_or(target_heading);
-    target.last_heading = Some(target_heading);
-    let delta_heading = angle_diff(target_heading, last_heading);
-    let av = angular_velocity() * TICK_LENGTH + delta_heading;
-    let aa = max_angular_acceleration() * TICK_LENGTH * TICK_LENGTH;
-    let time_to_stop = av.abs() / aa;
-    let heading_when_stopped = heading() + av * time_to_stop
-        - 0.5 * aa * av.signum() * (time_to_stop.powi(2) + time_to_stop);
-    let av_next_tick = av + aa * av.signum();
-    let heading_next_tick = heading() + av_next_tick - aa * av.signum();
-    let time_to_stop_later = av_next_tick.abs() / aa;
-    let heading_when_stopped_later = heading_next_tick + av_next_tick * time_to_stop_later
-        - 0.5 * aa * (time_to_stop_later.powi(2) + time_to_stop_later) * av_next_tick.signum();
-    let error = angle_diff(target_heading, heading_when_stopped);
-    let error_later = angle_diff(target_heading, heading_when_stopped_later);
-    let error_per_tick =
-        (error * 2.0 / (time_to_stop.powi(2) + time_to_stop)) / TICK_LENGTH / TICK_LENGTH;
-    let accel = if error.signum() != error_later.signum() {
-        max_angular_acceleration() * error.signum() - error_per_tick
-    } else {
-        -max_angular_acceleration() * error.signum()
-    };
-    torque(accel);
-}
-
-fn time_to_turn_to(target_heading: f64) -> f64 {
-    let av = angular_velocity() * TICK_LENGTH;
-    let curr_error = angle_diff(target_heading, heading());
-    let aa = max_angular_acceleration() * TICK_LENGTH * TICK_LENGTH;
-
-    let accel_sign = curr_error.signum() * -1.0;
-    let passed = ((-(aa / 2.0 + av)
-        + ((aa / 2.0 + av).powi(2) + 2.0 * aa * curr_error.abs()).sqrt() * accel_sign)
-        / aa)
-        .ceil()
-        .abs();
-    passed * TICK_LENGTH
-}
 fn lead_target(target_position: Vec2, target_velocity: Vec2, bullet_speed: f64) -> (f64, Vec2) {
     let dp = target_position - position();
     let dv = target_velocity - velocity();
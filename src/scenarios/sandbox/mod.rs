@@ -1 +1,4 @@
 pub mod kalman_test;
+pub mod turn;
+pub mod trajectory;
+pub mod pid_tune;
@@ -0,0 +1,77 @@
+use oort_api::prelude::*;
+
+use crate::pid::{GainSchedule, RelayTuner, PID};
+
+const TUNING_CYCLES: usize = 4;
+const STEP_HEADINGS: [f64; 4] = [PI / 2.0, -PI / 4.0, PI, 0.1];
+const SETTLE_ERROR: f64 = 1e-3;
+
+/// Relay-tunes a heading controller on whatever ship this runs on, then steps the tuned
+/// and the scheduled controllers through the same heading changes and reports settle
+/// times.
+pub struct Test {
+    tuner: RelayTuner,
+    tuned: Option<PID>,
+    scheduled: PID,
+    step: usize,
+    step_start: u32,
+}
+impl Default for Test {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Test {
+    pub fn new() -> Test {
+        Test {
+            tuner: RelayTuner::new(max_angular_acceleration() / 4.0, 0.01, TUNING_CYCLES),
+            tuned: None,
+            scheduled: PID::for_class(class()),
+            step: 0,
+            step_start: current_tick(),
+        }
+    }
+    pub fn tick(&mut self) {
+        if self.tuned.is_none() {
+            torque(self.tuner.update(angle_diff(heading(), 0.0)));
+            if !self.tuner.done() {
+                return;
+            }
+            let Some(gains) = self.tuner.gains() else {
+                debug!("no limit cycle, retrying");
+                self.tuner = RelayTuner::new(max_angular_acceleration() / 4.0, 0.01, TUNING_CYCLES);
+                return;
+            };
+            debug!("ultimate {:?}", self.tuner.ultimate());
+            debug!("tuned {:?}", gains);
+            let mut pid = PID::for_class(class());
+            pid.set_gains(gains);
+            self.tuned = Some(pid);
+            self.step_start = current_tick();
+        }
+        // The tuned gains run the course first, then the class schedule.
+        if self.step >= 2 * STEP_HEADINGS.len() {
+            debug!("all steps done");
+            return;
+        }
+        let target = STEP_HEADINGS[self.step % STEP_HEADINGS.len()];
+        let error = angle_diff(heading(), target);
+        let pid = if self.step < STEP_HEADINGS.len() {
+            self.tuned.as_mut().unwrap()
+        } else {
+            self.scheduled
+                .schedule(&GainSchedule::heading(class()), error.abs());
+            &mut self.scheduled
+        };
+        torque(pid.update(error));
+        let elapsed = current_tick() - self.step_start;
+        debug!("step {} error {:.4} tick {}", self.step, error, elapsed);
+        if error.abs() < SETTLE_ERROR && angular_velocity().abs() < SETTLE_ERROR {
+            debug!("step {} settled in {} ticks", self.step, elapsed);
+            pid.reset();
+            self.step += 1;
+            self.step_start = current_tick();
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use oort_api::prelude::*;
+
+use crate::utils::{Arrival, Autopilot, FriendlyHulls, TrajectoryGoal};
+
+const ARRIVED_DISTANCE: f64 = 5.0;
+const ARRIVED_SPEED: f64 = 1.0;
+
+/// Flies a fighter through a course of stop, fly-through and rendezvous legs and reports
+/// how long each took and how far off the final state was.
+pub struct Test {
+    leg: usize,
+    leg_start: u32,
+    spawn: Vec2,
+    autopilot: Autopilot,
+    hulls: FriendlyHulls,
+}
+impl Default for Test {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Test {
+    pub fn new() -> Test {
+        debug!("spawn fighter team 0 position (0, 0) heading 0");
+        Test {
+            leg: 0,
+            leg_start: current_tick(),
+            spawn: position(),
+            autopilot: Autopilot::new(),
+            hulls: FriendlyHulls::new(),
+        }
+    }
+    pub fn tick(&mut self) {
+        let elapsed = current_tick() - self.leg_start;
+        let Some(goal) = self.goal(elapsed) else {
+            debug!("course done");
+            self.autopilot
+                .fly(&TrajectoryGoal::stop_at(self.spawn), &self.hulls);
+            return;
+        };
+        draw_triangle(goal.position, 20.0, 0x00ff00);
+        draw_line(position(), goal.position, 0x00ff00);
+        self.autopilot.fly(&goal, &self.hulls);
+        let distance = position().distance(goal.position);
+        let speed_error = (velocity() - goal.velocity).length();
+        debug!("leg {} {:?}, tick {}", self.leg, goal.arrival, elapsed);
+        debug!("distance {:.1} speed error {:.1}", distance, speed_error);
+        let arrived = distance < ARRIVED_DISTANCE
+            && (goal.arrival == Arrival::FlyThrough || speed_error < ARRIVED_SPEED);
+        if arrived {
+            debug!(
+                "leg {} done in {} ticks, speed error {:.2}",
+                self.leg, elapsed, speed_error
+            );
+            self.leg += 1;
+            self.leg_start = current_tick();
+        }
+    }
+    fn goal(&self, elapsed: u32) -> Option<TrajectoryGoal> {
+        let t = elapsed as f64 * TICK_LENGTH;
+        let origin = self.spawn;
+        match self.leg {
+            0 => Some(TrajectoryGoal::stop_at(origin + vec2(2000.0, 0.0))),
+            1 => Some(TrajectoryGoal::stop_at(origin + vec2(2000.0, 1500.0))),
+            2 => Some(TrajectoryGoal::fly_through(origin + vec2(0.0, 0.0))),
+            3 => {
+                let velocity = vec2(-50.0, 80.0);
+                Some(TrajectoryGoal::rendezvous(
+                    origin + vec2(-1000.0, -1000.0) + velocity * t,
+                    velocity,
+                ))
+            }
+            _ => None,
+        }
+    }
+}
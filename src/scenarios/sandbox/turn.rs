@@ -0,0 +1,73 @@
+use oort_api::prelude::*;
+
+use crate::utils::{align, ticks_to_align, HeadingGoal};
+
+const SPIN_UP_RATES: [f64; 5] = [0.0, 1.0, -2.0, 4.0, -6.0];
+const SPIN_UP_TICKS: u32 = 30;
+const SETTLE_ERROR: f64 = 1e-3;
+
+/// Spins a fighter up to a range of angular velocities, then hands it to `align` and
+/// compares how long it really takes to settle with what `ticks_to_align` predicted.
+pub struct Test {
+    trial: usize,
+    phase_start: u32,
+    goal: Option<HeadingGoal>,
+    predicted: u32,
+}
+impl Default for Test {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Test {
+    pub fn new() -> Test {
+        Test {
+            trial: 0,
+            phase_start: current_tick(),
+            goal: None,
+            predicted: 0,
+        }
+    }
+    pub fn tick(&mut self) {
+        let Some(&rate) = SPIN_UP_RATES.get(self.trial) else {
+            debug!("all trials done");
+            return;
+        };
+        let elapsed = current_tick() - self.phase_start;
+        let Some(goal) = self.goal else {
+            torque((rate - angular_velocity()) / TICK_LENGTH);
+            if elapsed >= SPIN_UP_TICKS {
+                let goal = HeadingGoal::moving(heading() + PI / 2.0, rate / 4.0, 0.0);
+                self.predicted = ticks_to_align(
+                    &goal,
+                    heading(),
+                    angular_velocity(),
+                    max_angular_acceleration(),
+                );
+                self.goal = Some(goal);
+                self.phase_start = current_tick();
+            }
+            return;
+        };
+        let goal = HeadingGoal::moving(
+            goal.heading + goal.rate * elapsed as f64 * TICK_LENGTH,
+            goal.rate,
+            0.0,
+        );
+        align(&goal);
+        let error = angle_diff(heading(), goal.heading);
+        let closing = (angular_velocity() - goal.rate) * TICK_LENGTH;
+        debug!("trial {} spin {:.1} rad/s", self.trial, rate);
+        debug!("predicted {} ticks, elapsed {}", self.predicted, elapsed);
+        if error.abs() < SETTLE_ERROR && closing.abs() < SETTLE_ERROR {
+            debug!(
+                "trial {} settled in {} ticks, predicted {}",
+                self.trial, elapsed, self.predicted
+            );
+            self.trial += 1;
+            self.goal = None;
+            self.phase_start = current_tick();
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use oort_api::prelude::*;
 
-use crate::utils::{angle_at_distance, turn_to};
+use crate::utils::{angle_at_distance, thrust, turn_to};
 const BULLET_SPEED: f64 = 1000.0;
 pub struct Ship {
     fighter: Fighter,
@@ -58,7 +58,7 @@ impl Fighter {
                     contact.velocity.x,
                     contact.velocity.y,
                 ]);
-                accelerate(vec2(100.0, 0.0));
+                thrust(vec2(100.0, 0.0));
                 fire(0);
                 fire(1);
                 return;
@@ -104,7 +104,7 @@ impl Fighter {
             } else {
                 let random_offset = rand(-1.0, 1.0) * TAU / 162.0;
                 turn_to(random_offset);
-                accelerate(contact.position);
+                thrust(contact.position);
             }
             self.last_vel = contact.velocity - velocity();
         } else {
@@ -112,7 +112,7 @@ impl Fighter {
             set_radar_width(TAU / 60.0);
             let random_offset = rand(-1.0, 1.0) * TAU / 40.0;
             turn_to(random_offset);
-            accelerate(vec2(100.0, 0.0));
+            thrust(vec2(100.0, 0.0));
         }
     }
 }
@@ -147,7 +147,7 @@ impl Missile {
             if let Some(msg) = receive() {
                 (vec2(msg[0], msg[1]), vec2(msg[2], msg[3]))
             } else {
-                accelerate(vec2(100.0, 0.0).rotate(heading()));
+                thrust(vec2(100.0, 0.0).rotate(heading()));
                 return;
             }
         };
@@ -188,7 +188,7 @@ impl Missile {
         let accel = N * closing_speed * los_rate + N * nt.length() / 2.0 * los_rate;
         let a = vec2(100.0, accel).rotate(los);
         let a = vec2(400.0, 0.0).rotate(a.angle());
-        accelerate(a);
+        thrust(a);
         if dp.length() > 300.0 && fuel() > 0.0 {
             turn_to(a.angle());
         } else {
@@ -4,14 +4,16 @@ pub use super::frigate::Frigate;
 pub use super::missiles::cruiser_missile::CruiserMissile;
 pub use super::missiles::fighter_missile::FighterMissile;
 pub use super::missiles::frigate_missile::FrigateMissile;
+pub use super::missiles::torpedo::Torpedo;
 pub use super::missiles::Missile;
 pub use super::scenarios::Special;
 pub use oort_api::prelude::*;
 pub enum Ship {
-    Fighter(Fighter),
+    Fighter(Box<Fighter>),
     FighterMissile(FighterMissile),
     FrigateMissile(FrigateMissile),
     CruiserMissile(CruiserMissile),
+    Torpedo(Torpedo),
     Cruiser(Cruiser),
     Frigate(Frigate),
     Special(Special),
@@ -29,10 +31,10 @@ impl Ship {
             special => return Ship::Special(special),
         }
         match class() {
-            Class::Fighter => Ship::Fighter(Fighter::new()),
+            Class::Fighter => Ship::Fighter(Box::default()),
             Class::Cruiser => Ship::Cruiser(Cruiser::new()),
             Class::Frigate => Ship::Frigate(Frigate::new()),
-            Class::Torpedo => Ship::FighterMissile(FighterMissile::new()),
+            Class::Torpedo => Ship::Torpedo(Torpedo::new()),
             Class::Missile => match scenario_name() {
                 "tutorial_frigate" => Ship::FrigateMissile(FrigateMissile::new()),
                 "tutorial_cruiser" => Ship::CruiserMissile(CruiserMissile::new()),
@@ -47,6 +49,7 @@ impl Ship {
             Ship::FighterMissile(f) => f.tick(),
             Ship::FrigateMissile(f) => f.tick(),
             Ship::CruiserMissile(f) => f.tick(),
+            Ship::Torpedo(t) => t.tick(),
             Ship::Cruiser(c) => c.tick(),
             Ship::Frigate(f) => f.tick(),
             Ship::Special(s) => s.tick(),
@@ -0,0 +1,148 @@
+use crate::evasion::time_of_flight;
+use crate::utils::{class_guns, hull_bounding_radius, GunInfo, VecUtils};
+use oort_api::prelude::*;
+
+/// A shooter not seen for this long is forgotten.
+const SHOOTER_TIMEOUT_TICKS: u32 = 120;
+/// A bullet whose track, run backwards, passes this close to a shooter came from it.
+const ATTRIBUTION_DISTANCE: f64 = 100.0;
+/// How far ahead to predict shots.
+const LOOKAHEAD: f64 = 2.0;
+/// Most shots per gun to predict, however fast it reloads.
+const MAX_SHOTS_PER_GUN: u32 = 3;
+const DANGER_MARGIN: f64 = 10.0;
+
+/// Where and when a predicted shot will cross our track if we hold our current velocity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DangerZone {
+    pub center: Vec2,
+    pub radius: f64,
+    /// Seconds from now until the bullet gets there.
+    pub time: f64,
+    /// Direction the bullet travels in.
+    pub direction: Vec2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Shooter {
+    class: Class,
+    position: Vec2,
+    velocity: Vec2,
+    tick: u32,
+    /// Tick a shot from it was last seen to leave, which fixes its reload phase.
+    last_shot: Option<u32>,
+}
+
+impl Shooter {
+    fn predicted_position(&self, time: f64) -> Vec2 {
+        let age = (current_tick() - self.tick) as f64 * TICK_LENGTH;
+        self.position + self.velocity * (age + time)
+    }
+
+    /// Ticks from now until `gun` next fires, assuming it fires whenever it has
+    /// reloaded. Without a seen shot to go by it is assumed ready now.
+    fn next_shot(&self, gun: &GunInfo) -> u32 {
+        match self.last_shot {
+            Some(tick) => {
+                let since = current_tick() - tick;
+                (gun.reload_ticks - since % gun.reload_ticks) % gun.reload_ticks
+            }
+            None => 0,
+        }
+    }
+}
+
+/// Predicts enemy gunfire from the shooters' tracks and their class's guns, assuming each
+/// gun leads us perfectly on our current velocity and fires as soon as it reloads. Bullets
+/// seen on radar are traced back to their shooter to pin down its reload phase.
+#[derive(Debug, Default)]
+pub struct ShotPredictor {
+    shooters: Vec<Shooter>,
+}
+
+impl ShotPredictor {
+    pub fn new() -> ShotPredictor {
+        ShotPredictor {
+            shooters: Vec::new(),
+        }
+    }
+
+    pub fn observe(&mut self, contact: &ScanResult) {
+        self.shooters
+            .retain(|s| current_tick() - s.tick < SHOOTER_TIMEOUT_TICKS);
+        match contact.class {
+            Class::Fighter | Class::Frigate | Class::Cruiser => {
+                let class = contact.class;
+                let last_shot = self
+                    .shooters
+                    .iter()
+                    .position(|s| {
+                        s.class == class
+                            && s.predicted_position(0.0).distance(contact.position)
+                                < ATTRIBUTION_DISTANCE
+                    })
+                    .and_then(|i| self.shooters.remove(i).last_shot);
+                self.shooters.push(Shooter {
+                    class,
+                    position: contact.position,
+                    velocity: contact.velocity,
+                    tick: current_tick(),
+                    last_shot,
+                });
+            }
+            Class::Unknown => self.observe_bullet(contact.position, contact.velocity),
+            _ => {}
+        }
+    }
+
+    /// Traces a bullet back to the shooter it passes closest to and records when it left.
+    fn observe_bullet(&mut self, bullet_position: Vec2, bullet_velocity: Vec2) {
+        let traced = self.shooters.iter_mut().filter_map(|s| {
+            let dp = bullet_position - s.predicted_position(0.0);
+            let dv = bullet_velocity - s.velocity;
+            let age = dp.dot(dv) / dv.square_magnitude().max(1e-9);
+            let miss = (dp - dv * age).length();
+            (age > 0.0 && miss < ATTRIBUTION_DISTANCE).then_some((s, age, miss))
+        });
+        if let Some((shooter, age, _)) = traced.min_by(|a, b| a.2.partial_cmp(&b.2).unwrap()) {
+            shooter.last_shot =
+                Some(current_tick().saturating_sub((age / TICK_LENGTH).round() as u32));
+        }
+    }
+
+    /// Every predicted shot in the next `LOOKAHEAD` seconds that would hit us if we held
+    /// our current velocity.
+    pub fn danger_zones(&self) -> Vec<DangerZone> {
+        let radius = hull_bounding_radius(class()).unwrap_or(10.0) + DANGER_MARGIN;
+        let mut zones = Vec::new();
+        for shooter in &self.shooters {
+            for gun in class_guns(shooter.class) {
+                let first = shooter.next_shot(&gun);
+                for k in 0..MAX_SHOTS_PER_GUN {
+                    let fire_time = (first + k * gun.reload_ticks) as f64 * TICK_LENGTH;
+                    if fire_time > LOOKAHEAD {
+                        break;
+                    }
+                    let muzzle = shooter.predicted_position(fire_time);
+                    let us = position() + velocity() * fire_time;
+                    let Some(flight_time) = time_of_flight(
+                        us - muzzle,
+                        velocity() - shooter.velocity,
+                        gun.bullet_speed,
+                    )
+                    .filter(|&t| t < gun.bullet_lifetime) else {
+                        break;
+                    };
+                    let center = us + velocity() * flight_time;
+                    zones.push(DangerZone {
+                        center,
+                        radius,
+                        time: fire_time + flight_time,
+                        direction: (center - muzzle).normalize(),
+                    });
+                }
+            }
+        }
+        zones
+    }
+}
@@ -1,5 +1,7 @@
+use std::cell::Cell;
 use std::collections::VecDeque;
 
+use crate::launch_envelope::Envelope;
 use crate::utils::angle_at_distance;
 use crate::utils::bullet_speeds;
 use crate::utils::class_max_acceleration;
@@ -7,12 +9,16 @@ use crate::utils::draw_curve;
 use crate::utils::draw_points;
 use crate::utils::gun_color;
 use crate::utils::gun_offsets;
+use crate::utils::HeadingGoal;
 use crate::utils::VecUtils;
+use crate::validation::{kinematics, plausibility, Validation};
 use oort_api::prelude::*;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Target {
     pub position: Vec2,
+    /// Where the radar last saw it. `position` is extrapolated from here between returns.
+    pub last_position: Vec2,
     pub velocity: Vec2,
     pub last_velocity: Vec2,
     pub acceleration: Vec2,
@@ -23,12 +29,17 @@ pub struct Target {
     pub tick_updated: u32,
     pub history: VecDeque<Vec2>,
     pub future_positions: VecDeque<(Vec2, u32)>,
+    pub validation: Validation,
+    /// Launch envelope last worked out against this target, with the tick and missile
+    /// class it was for.
+    pub envelope: Cell<Option<(u32, Class, Envelope)>>,
 }
 
 impl Target {
     pub fn new(position: Vec2, velocity: Vec2, class: Class) -> Target {
         Target {
             position,
+            last_position: position,
             velocity,
             last_velocity: velocity,
             acceleration: Vec2::zero(),
@@ -39,28 +50,36 @@ impl Target {
             tick_updated: current_tick(),
             history: VecDeque::new(),
             future_positions: VecDeque::new(),
+            validation: Validation::new(),
+            envelope: Cell::new(None),
         }
     }
 
-    pub fn sanity_check(&self, new_position: Vec2, new_velocity: Vec2, new_class: Class) -> bool {
-        if self.class != new_class {
-            return false;
-        }
+    /// Whether a `new_class` contact at `new_position` with `new_velocity` could be this
+    /// track, judged from its last return.
+    pub fn consistent_with(
+        &self,
+        new_position: Vec2,
+        new_velocity: Vec2,
+        new_class: Class,
+    ) -> bool {
         let dt = (current_tick() - self.tick_updated) as f64 * TICK_LENGTH;
-        let acceleration = (new_velocity - self.last_velocity) / dt;
-        let max_acceleration = class_max_acceleration(new_class);
-        if acceleration.length() > max_acceleration {
-            return false;
-        }
-        if (new_position - self.position).length() > new_velocity.length() * dt * 4.0 {
-            return false;
-        }
-        true
+        self.class == new_class
+            && kinematics(
+                self.class,
+                self.last_position,
+                self.last_velocity,
+                dt,
+                new_position,
+                new_velocity,
+            )
+            .is_none()
     }
 
     pub fn update(&mut self, new_position: Vec2, new_velocity: Vec2) {
         let dt = (current_tick() - self.tick_updated) as f64 * TICK_LENGTH;
         self.position = new_position;
+        self.last_position = new_position;
         self.velocity = new_velocity;
         self.last_acceleration = self.acceleration;
         self.acceleration = (self.velocity - self.last_velocity) / dt;
@@ -78,6 +97,22 @@ impl Target {
         self.tick_updated = current_tick();
     }
 
+    /// Updates from a radar return, first checking it is consistent with the track so
+    /// far. Suspect returns still update the track but keep it quarantined.
+    pub fn observe(&mut self, contact: &ScanResult) {
+        let dt = (current_tick() - self.tick_updated) as f64 * TICK_LENGTH;
+        if dt > 0.0 {
+            self.validation.record(plausibility(
+                self.class,
+                self.last_position,
+                self.last_velocity,
+                dt,
+                contact,
+            ));
+        }
+        self.update(contact.position, contact.velocity);
+    }
+
     pub fn tick(&mut self, i: usize) {
         self.velocity += self.acceleration * TICK_LENGTH;
         self.position += self.velocity * TICK_LENGTH;
@@ -117,6 +152,22 @@ impl Target {
         set_radar_min_distance((self.position - position()).length() - 20.0);
     }
 
+    /// Heading to the latest lead point and the rate it has been moving at, for `align`.
+    pub fn heading_goal(&self) -> Option<HeadingGoal> {
+        let nf = self.future_positions.len();
+        if nf == 0 {
+            return None;
+        }
+        let target_heading = (self.future_positions[nf - 1].0 - position()).angle();
+        let rate = if nf >= 2 {
+            let last_heading = (self.future_positions[nf - 2].0 - position()).angle();
+            angle_diff(last_heading, target_heading) / TICK_LENGTH
+        } else {
+            0.0
+        };
+        Some(HeadingGoal::moving(target_heading, rate, 0.0))
+    }
+
     pub fn lead(&mut self, gun: usize) -> Vec2 {
         let gun_offset = gun_offsets(gun);
         let gun_position = position() - gun_offset.rotate(heading());
@@ -146,10 +197,10 @@ impl Target {
             // let angle = future_position.angle();
             // let miss_by = angle_diff(angle, heading()) * future_position.length();
             // if reload_ticks(gun) == 0 && (miss_by.abs() < 10.0 || gun != 0) {
-                self.future_positions.push_back((
-                    adjusted_position,
-                    current_tick() + (time_to_target / TICK_LENGTH) as u32,
-                ));
+            self.future_positions.push_back((
+                adjusted_position,
+                current_tick() + (time_to_target / TICK_LENGTH) as u32,
+            ));
             // }
             self.future_positions
                 .retain(|&(_, tick)| tick >= current_tick());
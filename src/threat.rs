@@ -0,0 +1,145 @@
+use crate::priority::{priority, PriorityWeights};
+use crate::target::Target;
+use crate::utils::class_guns;
+use oort_api::prelude::*;
+
+/// Inside this range, guns are for shooting down missiles and torpedoes first.
+const POINT_DEFENCE_RANGE: f64 = 1500.0;
+/// Closing speeds below this are treated as this, so a target holding range still counts.
+const MIN_CLOSING_SPEED: f64 = 50.0;
+
+/// Engagement zone a target is in, as seen from this ship.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zone {
+    /// Point defence range.
+    Short,
+    /// Within reach of the main gun.
+    Medium,
+    /// Beyond the guns: missiles only.
+    Long,
+}
+
+/// Zone boundaries of a class, from its guns.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Zones {
+    pub short: f64,
+    pub medium: f64,
+}
+
+impl Zones {
+    pub fn for_class(class: Class) -> Zones {
+        let medium = class_guns(class)
+            .iter()
+            .map(|g| g.range())
+            .fold(0.0, f64::max);
+        Zones {
+            short: POINT_DEFENCE_RANGE.min(medium),
+            medium,
+        }
+    }
+
+    pub fn zone(&self, target: &Target) -> Zone {
+        let distance = position().distance(target.position);
+        if distance < self.short {
+            Zone::Short
+        } else if distance < self.medium {
+            Zone::Medium
+        } else {
+            Zone::Long
+        }
+    }
+}
+
+/// How much of a threat `class` is, relative to a fighter.
+fn class_weight(class: Class) -> f64 {
+    match class {
+        Class::Torpedo => 6.0,
+        Class::Missile => 4.0,
+        Class::Cruiser => 3.0,
+        Class::Frigate => 2.0,
+        Class::Fighter => 1.0,
+        _ => 0.5,
+    }
+}
+
+/// How urgently `target` needs dealing with: its class weight over the time it needs to
+/// reach us.
+pub fn threat_score(target: &Target) -> f64 {
+    let dp = target.position - position();
+    let closing = -(target.velocity - velocity()).dot(dp) / dp.length().max(1.0);
+    let time_to_reach = dp.length() / closing.max(MIN_CLOSING_SPEED);
+    class_weight(target.class) / (1.0 + time_to_reach)
+}
+
+/// Something that can be pointed at a target: a gun or a radio channel of missiles.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeaponSlot {
+    pub index: usize,
+    pub zones: Vec<Zone>,
+    /// Whether it can engage missiles and torpedoes.
+    pub point_defence: bool,
+    /// Speed of its bullets, for hit probability. `None` for missiles.
+    pub bullet_speed: Option<f64>,
+}
+
+impl WeaponSlot {
+    fn can_engage(&self, target: &Target, zones: &Zones) -> bool {
+        let ordnance = [Class::Missile, Class::Torpedo].contains(&target.class);
+        (self.point_defence || !ordnance) && self.zones.contains(&zones.zone(target))
+    }
+}
+
+/// Pairs each weapon with the target it can engage that ranks highest under `weights`,
+/// spreading weapons over targets: each weapon already on a target divides its score for
+/// the next one.
+/// Targets still in quarantine are left alone.
+/// Returns `(weapon index, target index)` pairs; weapons with nothing in their zones are
+/// left out.
+pub fn assign_weapons(
+    targets: &[Target],
+    weapons: &[WeaponSlot],
+    zones: &Zones,
+    weights: &PriorityWeights,
+) -> Vec<(usize, usize)> {
+    let mut counts = vec![0; targets.len()];
+    let mut assignments = Vec::new();
+    for weapon in weapons {
+        let best = targets
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.validation.is_confirmed() && weapon.can_engage(t, zones))
+            .map(|(i, t)| {
+                let score = priority(t, weapon.bullet_speed, weights);
+                (i, score / (1 + counts[i]) as f64)
+            })
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        if let Some((i, _)) = best {
+            counts[i] += 1;
+            assignments.push((weapon.index, i));
+        }
+    }
+    assignments
+}
+
+/// Station that keeps threats in the zones that favour this ship: the most threatening
+/// enemy capital ship at `standoff` of the main gun's reach, or failing that, fighters that
+/// got into point-defence range pushed back out of it. `None` when nothing calls for a move.
+pub fn zone_station(targets: &[Target], zones: &Zones, standoff: f64) -> Option<Vec2> {
+    let most_threatening = |classes: &[Class]| {
+        targets
+            .iter()
+            .filter(|t| classes.contains(&t.class))
+            .max_by(|a, b| threat_score(a).partial_cmp(&threat_score(b)).unwrap())
+    };
+    if let Some(t) = most_threatening(&[Class::Frigate, Class::Cruiser]) {
+        let away = (position() - t.position).normalize();
+        Some(t.position + away * zones.medium * standoff)
+    } else if let Some(t) =
+        most_threatening(&[Class::Fighter]).filter(|t| zones.zone(t) == Zone::Short)
+    {
+        let away = (position() - t.position).normalize();
+        Some(position() + away * zones.short)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,162 @@
+use crate::launch_envelope::MissileParams;
+use crate::messages::Message;
+use crate::target::Target;
+use oort_api::prelude::*;
+
+const TRACK_MATCH_DISTANCE: f64 = 300.0;
+const KILL_RADIUS: f64 = 300.0;
+const TRACK_TIMEOUT: u32 = 300;
+const TRACK_LOSS_TICKS: u32 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Track {
+    pub class: Class,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub tick_updated: u32,
+}
+
+impl Track {
+    pub fn predicted_position(&self) -> Vec2 {
+        self.position + self.velocity * (current_tick() - self.tick_updated) as f64 * TICK_LENGTH
+    }
+}
+
+/// A missile's copy of the fleet's tracks, assembled from the `Track`, `Order` and
+/// `Kill` messages its launcher broadcasts. Used to pick a new target when the
+/// current one dies or is lost.
+#[derive(Debug)]
+pub struct TrackPicture {
+    tracks: Vec<Track>,
+}
+impl Default for TrackPicture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TrackPicture {
+    pub fn new() -> TrackPicture {
+        TrackPicture { tracks: Vec::new() }
+    }
+
+    /// Folds a received message into the picture. Returns the position of a reported
+    /// kill so the caller can drop its own target if it was the one destroyed.
+    pub fn handle(&mut self, message: &Message) -> Option<Vec2> {
+        match *message {
+            Message::Track {
+                class,
+                position,
+                velocity,
+            } => self.update(class, position, velocity),
+            Message::Order(order) => self.update(Class::Unknown, order.position, order.velocity),
+            Message::Target { position, velocity } => {
+                self.update(Class::Unknown, position, velocity)
+            }
+            Message::Kill { position } => {
+                self.remove_near(position);
+                return Some(position);
+            }
+            Message::Impact { .. }
+            | Message::Formation(_)
+            | Message::Roster(_)
+            | Message::RoleOrder(_)
+            | Message::Launch(_) => {}
+        }
+        None
+    }
+
+    /// Folds `message` into the picture and reports whether `target` should be dropped,
+    /// either because it was reported killed or because nothing has updated it recently.
+    pub fn target_lost(&mut self, message: Option<&Message>, target: &Option<Target>) -> bool {
+        let kill = message.and_then(|m| self.handle(m));
+        let Some(target) = target else {
+            return false;
+        };
+        if kill.is_some_and(|k| target.position.distance(k) < KILL_RADIUS) {
+            debug!("target killed, retargeting");
+            return true;
+        }
+        if current_tick() - target.tick_updated > TRACK_LOSS_TICKS {
+            debug!("track lost, retargeting");
+            self.remove_near(target.position);
+            return true;
+        }
+        false
+    }
+
+    pub fn update(&mut self, class: Class, position: Vec2, velocity: Vec2) {
+        if [Class::Missile, Class::Torpedo].contains(&class) {
+            return;
+        }
+        self.tracks
+            .retain(|t| current_tick() - t.tick_updated < TRACK_TIMEOUT);
+        let existing = self
+            .tracks
+            .iter_mut()
+            .find(|t| t.predicted_position().distance(position) < TRACK_MATCH_DISTANCE);
+        if let Some(track) = existing {
+            if class != Class::Unknown {
+                track.class = class;
+            }
+            track.position = position;
+            track.velocity = velocity;
+            track.tick_updated = current_tick();
+        } else {
+            self.tracks.push(Track {
+                class,
+                position,
+                velocity,
+                tick_updated: current_tick(),
+            });
+        }
+    }
+
+    pub fn remove_near(&mut self, position: Vec2) {
+        self.tracks
+            .retain(|t| t.predicted_position().distance(position) > KILL_RADIUS);
+    }
+
+    /// Picks the track with the shortest intercept time among those this missile can
+    /// still reach with its remaining fuel.
+    pub fn best_target(&self) -> Option<Track> {
+        self.best_target_of(&[])
+    }
+
+    /// Like `best_target`, restricted to `classes` when any such track is known.
+    pub fn best_target_of(&self, classes: &[Class]) -> Option<Track> {
+        let preferred = self.tracks.iter().any(|t| classes.contains(&t.class));
+        self.tracks
+            .iter()
+            .filter(|t| !preferred || classes.contains(&t.class))
+            .filter_map(|t| reachable(t).map(|time| (t, time)))
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(t, _)| Track {
+                position: t.predicted_position(),
+                tick_updated: current_tick(),
+                ..*t
+            })
+    }
+
+    /// The track closest to `position`, if one is within matching distance.
+    pub fn track_near(&self, position: Vec2) -> Option<Track> {
+        self.tracks
+            .iter()
+            .map(|t| (t, t.predicted_position().distance(position)))
+            .filter(|(_, d)| *d < TRACK_MATCH_DISTANCE)
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(t, _)| *t)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
+}
+
+/// Time for this missile to meet the track with the fuel it has left, or `None` if it
+/// is outside the reachable envelope.
+fn reachable(track: &Track) -> Option<f64> {
+    let dp = track.predicted_position() - position();
+    let dv = track.velocity - velocity();
+    MissileParams::current().intercept_time(dp, dv, 0.0)
+}
@@ -0,0 +1,109 @@
+use oort_api::prelude::*;
+
+use crate::target::Target;
+use crate::utils::{
+    align, class_info, stopping_speed, thrust_toward, Autopilot, FriendlyHulls, HeadingGoal,
+    TrajectoryGoal,
+};
+
+const EDGE_MARGIN: f64 = 500.0;
+
+/// The square map centred on the origin, with a margin kept clear of its edges.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Arena {
+    pub half_size: f64,
+    pub margin: f64,
+}
+
+impl Arena {
+    pub fn current() -> Arena {
+        Arena {
+            half_size: world_size() / 2.0,
+            margin: EDGE_MARGIN,
+        }
+    }
+
+    fn limit(&self) -> f64 {
+        self.half_size - self.margin
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.x.abs() <= self.limit() && point.y.abs() <= self.limit()
+    }
+
+    /// Nearest point to `point` inside the margin.
+    pub fn clamp(&self, point: Vec2) -> Vec2 {
+        vec2(
+            point.x.clamp(-self.limit(), self.limit()),
+            point.y.clamp(-self.limit(), self.limit()),
+        )
+    }
+
+    /// Trims `a` so that a ship braking at `braking` can always stop short of the margin.
+    /// Each axis is limited on its own: the speed toward an edge may not grow past what
+    /// can be shed in the room left, and is pulled back down once it has.
+    pub fn constrain(&self, position: Vec2, velocity: Vec2, a: Vec2, braking: f64) -> Vec2 {
+        let axis = |p: f64, v: f64, a: f64| {
+            let max_speed = stopping_speed((self.limit() - p).max(0.0), braking);
+            let min_speed = -stopping_speed((self.limit() + p).max(0.0), braking);
+            a.clamp((min_speed - v) / TICK_LENGTH, (max_speed - v) / TICK_LENGTH)
+        };
+        vec2(
+            axis(position.x, velocity.x, a.x),
+            axis(position.y, velocity.y, a.y),
+        )
+    }
+}
+
+/// Commands acceleration `a`, trimmed to keep the ship inside the arena. All movement
+/// helpers go through here rather than calling `accelerate` directly. Missiles and
+/// torpedoes are left unclamped: they are spent long before the walls matter and terminal
+/// guidance must be free to chase a target there.
+pub fn thrust(a: Vec2) {
+    if [Class::Missile, Class::Torpedo].contains(&class()) {
+        accelerate(a);
+        return;
+    }
+    let info = class_info(class());
+    let braking = if info.backward_acceleration > 0.0 {
+        info.backward_acceleration.min(info.lateral_acceleration)
+    } else {
+        info.lateral_acceleration
+    };
+    accelerate(Arena::current().constrain(position(), velocity(), a, braking));
+}
+
+/// Bearing of the main threat: toward the centre of the enemy tracks, each weighted by
+/// how close it is.
+pub fn threat_axis(targets: &[Target]) -> Option<f64> {
+    let (sum, weight) = targets
+        .iter()
+        .filter(|t| ![Class::Missile, Class::Torpedo].contains(&t.class))
+        .fold((vec2(0.0, 0.0), 0.0), |(sum, weight), t| {
+            let w = 1.0 / position().distance(t.position).max(1.0);
+            (sum + t.position * w, weight + w)
+        });
+    if weight == 0.0 {
+        return None;
+    }
+    Some((sum / weight - position()).angle())
+}
+
+/// Holds a capital ship on `station`, pulled inside the arena, turned to `heading` (for
+/// instance bow-on to the threat axis, or whatever `plan_heading` picks). With no heading
+/// the ship is free to turn however the move needs. The move keeps clear of `hulls`.
+pub fn keep_station(
+    autopilot: &mut Autopilot,
+    hulls: &FriendlyHulls,
+    station: Vec2,
+    heading: Option<f64>,
+) {
+    let goal = TrajectoryGoal::stop_at(Arena::current().clamp(station));
+    match heading {
+        Some(heading) => {
+            align(&HeadingGoal::fixed(heading));
+            thrust_toward(&goal, hulls);
+        }
+        None => autopilot.fly(&goal, hulls),
+    }
+}
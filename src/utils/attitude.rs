@@ -0,0 +1,197 @@
+use oort_api::prelude::*;
+
+const ALIGNED_ERROR: f64 = 1e-3;
+const MAX_PREDICTION_TICKS: u32 = 600;
+
+/// A heading to track: where it is this tick, how fast it is moving and how fast that
+/// rate is changing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeadingGoal {
+    pub heading: f64,
+    pub rate: f64,
+    pub acceleration: f64,
+}
+
+impl HeadingGoal {
+    pub fn fixed(heading: f64) -> HeadingGoal {
+        HeadingGoal {
+            heading,
+            rate: 0.0,
+            acceleration: 0.0,
+        }
+    }
+
+    pub fn moving(heading: f64, rate: f64, acceleration: f64) -> HeadingGoal {
+        HeadingGoal {
+            heading,
+            rate,
+            acceleration,
+        }
+    }
+
+    fn step(&mut self) {
+        self.rate += self.acceleration * TICK_LENGTH;
+        self.heading += self.rate * TICK_LENGTH;
+    }
+}
+
+/// Angular acceleration that brings a ship with the given heading and angular velocity
+/// onto `goal` in the fewest ticks.
+///
+/// Works on the error relative to the goal, in per-tick units. Each tick the closing rate
+/// can change by at most `budget`, so a rate of `n * budget + r` covers
+/// `(n + 1) * rate - budget * n * (n + 1) / 2` radians before it can be brought to zero.
+/// Inverting that gives the largest closing rate that still stops exactly on the goal;
+/// the controller asks for that rate next tick and saturates when it can't get there.
+pub fn attitude_acceleration(
+    goal: &HeadingGoal,
+    heading: f64,
+    angular_velocity: f64,
+    max_angular_acceleration: f64,
+) -> f64 {
+    let dt2 = TICK_LENGTH * TICK_LENGTH;
+    let error = angle_diff(heading, goal.heading);
+    let closing = (angular_velocity - goal.rate) * TICK_LENGTH;
+    let budget = (max_angular_acceleration - goal.acceleration.abs())
+        .max(0.1 * max_angular_acceleration)
+        * dt2;
+    let desired = error.signum() * stopping_rate(error.abs(), budget);
+    ((desired - closing) / dt2 + goal.acceleration)
+        .clamp(-max_angular_acceleration, max_angular_acceleration)
+}
+
+/// Largest per-tick closing rate from which `error` can be covered exactly while slowing
+/// down by at most `budget` per tick.
+pub fn stopping_rate(error: f64, budget: f64) -> f64 {
+    let n = (((8.0 * error / budget + 1.0).sqrt() - 1.0) / 2.0).floor();
+    (error + budget * n * (n + 1.0) / 2.0) / (n + 1.0)
+}
+
+/// Ticks the controller needs to settle on `goal` from the given state, found by running
+/// it forward on the same discrete model the simulator uses.
+pub fn ticks_to_align(
+    goal: &HeadingGoal,
+    heading: f64,
+    angular_velocity: f64,
+    max_angular_acceleration: f64,
+) -> u32 {
+    let mut goal = *goal;
+    let mut heading = heading;
+    let mut angular_velocity = angular_velocity;
+    for tick in 0..MAX_PREDICTION_TICKS {
+        let error = angle_diff(heading, goal.heading);
+        let closing = (angular_velocity - goal.rate) * TICK_LENGTH;
+        if error.abs() < ALIGNED_ERROR && closing.abs() < ALIGNED_ERROR {
+            return tick;
+        }
+        angular_velocity +=
+            attitude_acceleration(&goal, heading, angular_velocity, max_angular_acceleration)
+                * TICK_LENGTH;
+        heading += angular_velocity * TICK_LENGTH;
+        goal.step();
+    }
+    MAX_PREDICTION_TICKS
+}
+
+/// Applies the torque that tracks `goal` in minimum time.
+pub fn align(goal: &HeadingGoal) {
+    torque(attitude_acceleration(
+        goal,
+        heading(),
+        angular_velocity(),
+        max_angular_acceleration(),
+    ));
+}
+
+/// Predicted time for this ship to settle on `goal`.
+pub fn time_to_align(goal: &HeadingGoal) -> f64 {
+    ticks_to_align(
+        goal,
+        heading(),
+        angular_velocity(),
+        max_angular_acceleration(),
+    ) as f64
+        * TICK_LENGTH
+}
+
+/// Turns the ship to a fixed heading in the shortest time.
+pub fn turn_to(target_heading: f64) {
+    align(&HeadingGoal::fixed(target_heading));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fighter angular acceleration.
+    const MAX: f64 = TAU;
+
+    /// Steps the controller on the simulator's model for `ticks` and returns the goal
+    /// and final state, with the number of times the error changed sign near the goal.
+    fn run(goal: HeadingGoal, angular_velocity: f64, ticks: u32) -> (HeadingGoal, f64, f64, u32) {
+        let mut goal = goal;
+        let mut heading = 0.0;
+        let mut angular_velocity = angular_velocity;
+        let mut last_error = angle_diff(heading, goal.heading);
+        let mut crossings = 0;
+        for _ in 0..ticks {
+            angular_velocity +=
+                attitude_acceleration(&goal, heading, angular_velocity, MAX) * TICK_LENGTH;
+            heading += angular_velocity * TICK_LENGTH;
+            goal.step();
+            let error = angle_diff(heading, goal.heading);
+            let near = error.abs() < 0.5 && last_error.abs() < 0.5;
+            if near && error.abs() > ALIGNED_ERROR && error.signum() != last_error.signum() {
+                crossings += 1;
+            }
+            last_error = error;
+        }
+        (goal, heading, angular_velocity, crossings)
+    }
+
+    fn assert_settles(goal: HeadingGoal, angular_velocity: f64) {
+        // Spinning towards the goal faster than the ship can stop in the distance left
+        // forces a pass through it, and another for every full turn beyond that; any
+        // more is the controller ringing.
+        let closing = angular_velocity - goal.rate;
+        let distance = (closing.signum() * goal.heading).rem_euclid(TAU);
+        let stopping = closing * closing / (2.0 * MAX);
+        let allowed = if stopping > distance {
+            1 + ((stopping - distance) / TAU) as u32
+        } else {
+            0
+        };
+        let ticks = ticks_to_align(&goal, 0.0, angular_velocity, MAX);
+        assert!(
+            ticks < MAX_PREDICTION_TICKS,
+            "av {angular_velocity} never settles"
+        );
+        let (goal, heading, final_velocity, crossings) = run(goal, angular_velocity, ticks);
+        let error = angle_diff(heading, goal.heading);
+        let closing = (final_velocity - goal.rate) * TICK_LENGTH;
+        assert!(
+            error.abs() < ALIGNED_ERROR && closing.abs() < ALIGNED_ERROR,
+            "av {angular_velocity} off by {error} after {ticks} ticks"
+        );
+        assert!(
+            crossings <= allowed,
+            "av {angular_velocity} overshoots {crossings} times"
+        );
+    }
+
+    #[test]
+    fn fixed_goal_settles_without_overshoot() {
+        for i in -40..=40 {
+            assert_settles(HeadingGoal::fixed(1.0), i as f64 * 0.25);
+        }
+    }
+
+    #[test]
+    fn moving_goal_settles_without_overshoot() {
+        for rate in [-1.0, 0.5, 2.0] {
+            for i in -20..=20 {
+                assert_settles(HeadingGoal::moving(1.0, rate, 0.0), i as f64 * 0.5);
+            }
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use oort_api::prelude::*;
+
+use crate::utils::{
+    class_max_acceleration, decode_class_and_position, hull_bounding_radius, max_accelerate,
+    plan_path, turn_to, Hull, VecUtils, BEACON_CHANNEL,
+};
+
+/// How far ahead, in seconds, a collision has to be before it is avoided.
+const TIME_HORIZON: f64 = 3.0;
+const CLEARANCE: f64 = 20.0;
+const DEBRIS_RADIUS: f64 = 100.0;
+const OBSTACLE_TIMEOUT_TICKS: u32 = 30;
+/// A beacon this close to where the last one from the same class was predicted to be is
+/// taken to come from the same hull.
+const MATCH_DISTANCE: f64 = 100.0;
+const PROJECTION_PASSES: usize = 3;
+/// How long a freshly launched missile keeps its radio on the beacon channel. A radio
+/// hears one beacon a tick and hulls beacon every other tick or less, so this is enough
+/// to hear the launcher and the ships around it.
+const BEACON_LISTEN_TICKS: u32 = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Obstacle {
+    pub class: Class,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub heading: f64,
+    pub radius: f64,
+    pub tick: u32,
+}
+
+impl Obstacle {
+    fn predicted_position(&self) -> Vec2 {
+        self.position + self.velocity * ((current_tick() - self.tick) as f64 * TICK_LENGTH)
+    }
+}
+
+/// Friendly hulls heard on the beacon channel and debris seen on radar. The controller
+/// that owns it keeps clear of them with `avoid`, or, for a missile leaving its launcher,
+/// with `steer_around`.
+#[derive(Debug)]
+pub struct FriendlyHulls {
+    obstacles: Vec<Obstacle>,
+    /// For a missile just launched, the channel to hand the radio back to and the last
+    /// tick it listens for beacons.
+    beacon_window: Option<(usize, u32)>,
+}
+
+impl Default for FriendlyHulls {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FriendlyHulls {
+    pub fn new() -> FriendlyHulls {
+        FriendlyHulls {
+            obstacles: Vec::new(),
+            beacon_window: None,
+        }
+    }
+
+    /// For a missile just launched: keeps the radio on `BEACON_CHANNEL` for a few ticks
+    /// and then hands it to `channel`.
+    pub fn after_launch(channel: usize) -> FriendlyHulls {
+        set_radio_channel(BEACON_CHANNEL);
+        FriendlyHulls {
+            obstacles: Vec::new(),
+            beacon_window: Some((channel, current_tick() + BEACON_LISTEN_TICKS)),
+        }
+    }
+
+    /// Changes the channel the radio is handed to when the launch window closes, for a
+    /// missile that learns its channel from the launcher.
+    pub fn hand_over_to(&mut self, channel: usize) {
+        if let Some((_, until)) = self.beacon_window {
+            self.beacon_window = Some((channel, until));
+        }
+    }
+
+    /// Records a beacon in `msg`, what the radio heard this tick, while the launch window
+    /// is open. Returns `true` while the radio is still on the beacon channel, in which
+    /// case `msg` is no traffic from the missile's own channel.
+    pub fn listen(&mut self, msg: Option<&[u8; 32]>) -> bool {
+        let Some((channel, until)) = self.beacon_window else {
+            return false;
+        };
+        if current_tick() > until {
+            self.beacon_window = None;
+            return false;
+        }
+        if let Some(msg) = msg {
+            self.hear_beacon(msg);
+        }
+        if current_tick() == until {
+            set_radio_channel(channel);
+        }
+        true
+    }
+
+    /// Records a friendly hull from a `send_class_and_position` beacon. Beacons carry no
+    /// velocity, so it is estimated from the previous beacon of the same hull.
+    pub fn hear_beacon(&mut self, msg: &[u8; 32]) {
+        let Some((hull_class, hull_position, hull_heading)) = decode_class_and_position(msg) else {
+            return;
+        };
+        let Some(radius) = hull_bounding_radius(hull_class) else {
+            return;
+        };
+        // Our own beacon, from last tick.
+        if hull_class == class()
+            && hull_position.distance(position() - velocity() * TICK_LENGTH) < 5.0
+        {
+            return;
+        }
+        self.record(hull_class, hull_position, None, hull_heading, radius);
+    }
+
+    /// Records debris seen on radar.
+    pub fn observe_debris(&mut self, contact: &ScanResult) {
+        if contact.class == Class::Asteroid {
+            self.record(
+                contact.class,
+                contact.position,
+                Some(contact.velocity),
+                0.0,
+                DEBRIS_RADIUS,
+            );
+        }
+    }
+
+    pub fn obstacles(&self) -> &[Obstacle] {
+        &self.obstacles
+    }
+
+    fn record(
+        &mut self,
+        class: Class,
+        position: Vec2,
+        velocity: Option<Vec2>,
+        heading: f64,
+        radius: f64,
+    ) {
+        let o = &mut self.obstacles;
+        o.retain(|x| current_tick() - x.tick < OBSTACLE_TIMEOUT_TICKS);
+        let previous = o
+            .iter()
+            .position(|x| {
+                x.class == class && x.predicted_position().distance(position) < MATCH_DISTANCE
+            })
+            .map(|i| o.remove(i));
+        let velocity = velocity
+            .or_else(|| {
+                previous.map(|p| {
+                    (position - p.position)
+                        / ((current_tick() - p.tick).max(1) as f64 * TICK_LENGTH)
+                })
+            })
+            .unwrap_or(vec2(0.0, 0.0));
+        o.push(Obstacle {
+            class,
+            position,
+            velocity,
+            heading,
+            radius,
+            tick: current_tick(),
+        });
+    }
+
+    /// Adjusts the commanded acceleration `a` so that the velocity it leads to stays clear
+    /// of every known obstacle for `TIME_HORIZON`, in the manner of ORCA: each obstacle
+    /// rules out a half-plane of velocities, and the desired velocity is projected back
+    /// into the allowed region. The more agile ship takes the larger share of each
+    /// avoidance, so two of our fighters split it evenly and a fighter does nearly all of
+    /// it around a cruiser.
+    pub fn avoid(&self, a: Vec2) -> Vec2 {
+        let our_radius = hull_bounding_radius(class()).unwrap_or(10.0);
+        let planes = self
+            .obstacles
+            .iter()
+            .filter(|x| current_tick() - x.tick < OBSTACLE_TIMEOUT_TICKS)
+            .filter_map(|x| half_plane(x, our_radius))
+            .collect::<Vec<_>>();
+        if planes.is_empty() {
+            return a;
+        }
+        let mut desired = velocity() + a * TICK_LENGTH;
+        for _ in 0..PROJECTION_PASSES {
+            for &(point, normal) in &planes {
+                let violation = (desired - point).dot(normal);
+                if violation < 0.0 {
+                    desired -= normal * violation;
+                }
+            }
+        }
+        (desired - velocity()) / TICK_LENGTH
+    }
+
+    /// Steers toward the first waypoint of a collision-free path to `goal` around the
+    /// known hulls. Returns `false` without commanding anything when the straight line is
+    /// already clear.
+    pub fn steer_around(&self, goal: Vec2) -> bool {
+        let hulls = self
+            .obstacles
+            .iter()
+            .filter(|x| x.class != Class::Asteroid)
+            .map(|x| Hull {
+                class: x.class,
+                position: x.predicted_position(),
+                heading: x.heading,
+            })
+            .collect::<Vec<_>>();
+        let waypoint = plan_path(position(), goal, &hulls);
+        if waypoint == goal {
+            return false;
+        }
+        debug!("steering around friendly hull");
+        draw_line(position(), waypoint, 0xffff00);
+        draw_line(waypoint, goal, 0xffff00);
+        let dp = waypoint - position();
+        turn_to(dp.angle());
+        max_accelerate(dp);
+        true
+    }
+}
+
+/// Velocities allowed past `obstacle`, as a point on the boundary and the normal pointing
+/// into the allowed side. `None` when it is too far away to matter within the horizon.
+fn half_plane(obstacle: &Obstacle, our_radius: f64) -> Option<(Vec2, Vec2)> {
+    let rel_pos = obstacle.predicted_position() - position();
+    let rel_vel = velocity() - obstacle.velocity;
+    let radius = our_radius + obstacle.radius + CLEARANCE;
+    let dist_sq = rel_pos.square_magnitude();
+    if rel_pos.length() - radius > rel_vel.length() * TIME_HORIZON {
+        return None;
+    }
+    let (u, normal) = if dist_sq > radius * radius {
+        let w = rel_vel - rel_pos / TIME_HORIZON;
+        let w_len_sq = w.square_magnitude();
+        let dot = w.dot(rel_pos);
+        if dot < 0.0 && dot * dot > radius * radius * w_len_sq {
+            // Closest to the cut-off circle at the end of the horizon.
+            let w_len = w_len_sq.sqrt();
+            let normal = w / w_len;
+            (normal * (radius / TIME_HORIZON - w_len), normal)
+        } else {
+            // Closest to one of the cone's legs.
+            let leg = (dist_sq - radius * radius).sqrt();
+            let direction = if rel_pos.wedge(w) > 0.0 {
+                vec2(
+                    rel_pos.x * leg - rel_pos.y * radius,
+                    rel_pos.x * radius + rel_pos.y * leg,
+                ) / dist_sq
+            } else {
+                -vec2(
+                    rel_pos.x * leg + rel_pos.y * radius,
+                    -rel_pos.x * radius + rel_pos.y * leg,
+                ) / dist_sq
+            };
+            let u = direction * rel_vel.dot(direction) - rel_vel;
+            (u, vec2(-direction.y, direction.x))
+        }
+    } else {
+        // Already overlapping: get out within a tick.
+        let w = rel_vel - rel_pos / TICK_LENGTH;
+        let w_len = w.length().max(1e-9);
+        let normal = w / w_len;
+        (normal * (radius / TICK_LENGTH - w_len), normal)
+    };
+    let ours = class_max_acceleration(class());
+    let theirs = if obstacle.class == Class::Asteroid {
+        0.0
+    } else {
+        class_max_acceleration(obstacle.class)
+    };
+    let share = ours / (ours + theirs).max(1e-9);
+    Some((velocity() + u * share, normal))
+}
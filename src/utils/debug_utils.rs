@@ -35,12 +35,30 @@ pub fn draw_points(points: &VecDeque<(Vec2, u32)>, color: u32) {
     }
 }
 
+/// Half extents of a ship's hull along and across its heading.
+pub fn hull_size(class: Class) -> Option<(f64, f64)> {
+    match class {
+        Class::Fighter => Some(FIGHTER_SIZE),
+        Class::Frigate => Some(FRIGATE_SIZE),
+        Class::Cruiser => Some(CRUISER_SIZE),
+        _ => None,
+    }
+}
+
+/// How far off a ship's centre a round can pass and still be sure to hit: the narrower of
+/// its half extents.
+pub fn hull_radius(class: Class) -> Option<f64> {
+    hull_size(class).map(|(length, width)| length.min(width))
+}
+
+/// Radius of the circle taking in a ship's whole hull, for keeping clear of it.
+pub fn hull_bounding_radius(class: Class) -> Option<f64> {
+    hull_size(class).map(|(length, width)| length.hypot(width))
+}
+
 pub fn draw_collision_box(class: Class, position: Vec2, rotation: f64) {
-    let (width, height) = match class {
-        Class::Fighter => FIGHTER_SIZE,
-        Class::Frigate => FRIGATE_SIZE,
-        Class::Cruiser => CRUISER_SIZE,
-        _ => panic!("Invalid class: {:?}", class),
+    let Some((width, height)) = hull_size(class) else {
+        panic!("Invalid class: {:?}", class);
     };
     let corners = vec![
         vec2(-width, -height),
@@ -1,11 +1,27 @@
 use oort_api::prelude::*;
 
+pub mod arena;
+pub mod attitude;
+pub mod avoidance;
 pub mod debug_utils;
 pub mod movement;
+pub mod orientation;
+pub mod mpc;
+pub mod path_planning;
+pub mod registry;
+pub mod trajectory;
 pub mod vec_utils;
 
+pub use arena::*;
+pub use attitude::*;
+pub use avoidance::*;
 pub use debug_utils::*;
 pub use movement::*;
+pub use orientation::*;
+pub use mpc::*;
+pub use path_planning::*;
+pub use registry::*;
+pub use trajectory::*;
 pub use vec_utils::VecUtils;
 
 pub fn send_class_and_position() {
@@ -28,13 +44,7 @@ pub fn decode_class_and_position(msg: &[u8]) -> Option<(Class, Vec2, f64)> {
         debug!("Checksum failed");
         return None;
     }
-    let class_u8 = msg[0];
-    let class = match class_u8 {
-        0 => Class::Fighter,
-        1 => Class::Frigate,
-        2 => Class::Cruiser,
-        _ => Class::Unknown,
-    };
+    let class = class_from_u8(msg[0]);
     let x = f64::from_le_bytes(msg[8..16].try_into().unwrap());
     let y = f64::from_le_bytes(msg[16..24].try_into().unwrap());
     let position = vec2(x, y);
@@ -42,13 +52,27 @@ pub fn decode_class_and_position(msg: &[u8]) -> Option<(Class, Vec2, f64)> {
     Some((class, position, rotation))
 }
 
+pub fn class_from_u8(class_u8: u8) -> Class {
+    match class_u8 {
+        0 => Class::Fighter,
+        1 => Class::Frigate,
+        2 => Class::Cruiser,
+        5 => Class::Missile,
+        6 => Class::Torpedo,
+        _ => Class::Unknown,
+    }
+}
+
+/// How long `boost` keeps `Ability::Boost` active.
+pub const BOOST_TICKS: usize = 120;
+
 pub fn boost(cond: bool, boost_ticks: &mut Option<usize>) {
     if cond && boost_ticks.is_none() {
         activate_ability(Ability::Boost);
         *boost_ticks = Some(0);
     } else if let Some(&ticks) = boost_ticks.as_ref() {
         *boost_ticks = Some(ticks + 1);
-        if ticks >= 120 {
+        if ticks >= BOOST_TICKS {
             deactivate_ability(Ability::Boost);
         }
         if ticks > 600 {
@@ -98,12 +122,10 @@ pub fn gun_color(gun: usize) -> u32 {
 }
 
 pub fn class_max_acceleration(class: Class) -> f64 {
-    match class {
-            Class::Fighter => vec2(160.0, 30.).length(),
-            Class::Frigate => vec2(10.0, 5.0).length(),
-            Class::Missile => vec2(400.0, 100.0).length(),
-            Class::Cruiser => vec2(5.0, 2.5).length(),
-            Class::Torpedo => vec2(70.0, 20.0).length(),
-            _ => 0.0,
-    }
+    let info = class_info(class);
+    vec2(
+        info.forward_acceleration + info.boost_acceleration,
+        info.lateral_acceleration,
+    )
+    .length()
 }
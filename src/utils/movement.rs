@@ -1,6 +1,7 @@
 use oort_api::prelude::*;
 
-use crate::{target::Target, utils::VecUtils};
+use crate::target::Target;
+use crate::utils::{thrust, turn_to, VecUtils};
 
 pub fn boost_max_acceleration() -> Vec2 {
     if active_abilities().get_ability(Ability::Boost) {
@@ -37,83 +38,9 @@ pub fn max_accelerate(a: Vec2) {
         .y
         .clamp(-max_lateral_acceleration(), max_lateral_acceleration());
     let adjusted = vec2(x, y);
-    accelerate(adjusted.rotate(heading()));
+    thrust(adjusted.rotate(heading()));
 }
 
-/// Turns the ship by applying torque to reach the target heading in the shortest time.
-pub fn turn_to(target_heading: f64) {
-    let error = angle_diff(target_heading, heading());
-    let time_to_stop = angular_velocity().abs() / max_angular_acceleration();
-    let angle_while_stopping =
-        angular_velocity() * time_to_stop - 0.5 * max_angular_acceleration() * time_to_stop.powi(2);
-    let stopped_error = angle_diff(target_heading, heading() + angle_while_stopping);
-    let applied_torque = max_angular_acceleration() * error.signum();
-    if stopped_error * error.signum() < 0.0 {
-        torque(applied_torque);
-    } else {
-        torque(-applied_torque);
-    }
-}
-
-pub fn turn_to_simple(target_heading: f64) {
-    let error = angle_diff(heading(), target_heading);
-    turn(10.0 * error);
-}
-
-pub fn turn_to_faster(target: &Target) {
-    let nf = target.future_positions.len();
-    if nf == 0 {
-        return;
-    }
-    let target_heading = (target.future_positions[nf-1].0 - position()).angle();
-    let av = if nf >= 2 {
-        let last_heading = (target.future_positions[nf-2].0 - position()).angle();
-        let delta_heading = angle_diff(target_heading, last_heading);
-        angular_velocity() * TICK_LENGTH - delta_heading * TICK_LENGTH
-    } else {
-        angular_velocity() * TICK_LENGTH
-    };
-    let aa = max_angular_acceleration() * TICK_LENGTH * TICK_LENGTH;
-    let time_to_stop = av.abs() / aa;
-    let heading_when_stopped =
-        heading() + av * time_to_stop - 0.5 * aa * av.signum() * (time_to_stop.powi(2) + time_to_stop);
-    let av_next_tick = av + aa * av.signum();
-    let heading_next_tick = heading() + av_next_tick - aa * av.signum();
-    let time_to_stop_later = av_next_tick.abs() / aa;
-    let heading_when_stopped_later = heading_next_tick + av_next_tick * time_to_stop_later
-        - 0.5 * aa * (time_to_stop_later.powi(2) + time_to_stop_later) * av_next_tick.signum();
-    let error = angle_diff(target_heading, heading_when_stopped);
-    let error_later = angle_diff(target_heading, heading_when_stopped_later);
-    let error_per_tick =
-        (error * 2.0 / (time_to_stop.powi(2) + time_to_stop)) / TICK_LENGTH / TICK_LENGTH;
-    let accel = if error.signum() != error_later.signum() {
-        max_angular_acceleration() * error.signum() - error_per_tick
-    } else {
-        -max_angular_acceleration() * error.signum()
-    };
-    torque(accel);
-}
-
-pub fn turn_to_fast(target_heading: f64) {
-    let av = angular_velocity() * TICK_LENGTH;
-    let curr_error = angle_diff(target_heading, heading());
-    let aa = max_angular_acceleration() * TICK_LENGTH * TICK_LENGTH;
-
-    // let passed = (((8.0 * target_heading / aa + 1.0).sqrt() - 1.0) / 2.0).ceil();
-    let accel_sign = curr_error.signum() * -1.0;
-    let passed = ((-(aa / 2.0 + av)
-        + ((aa / 2.0 + av).powi(2) + 2.0 * aa * curr_error.abs()).sqrt() * accel_sign)
-        / aa)
-        .ceil()
-        .abs();
-    let heading_when_stopped =
-        heading() + av * passed + aa * accel_sign * (passed.powi(2) + passed) / 2.0;
-    let error = angle_diff(target_heading, heading_when_stopped).abs();
-    let error_per_tick = error * 2.0 / (passed.powi(2) + passed);
-    let accel =
-        (max_angular_acceleration() - error_per_tick / TICK_LENGTH / TICK_LENGTH) * accel_sign;
-    torque(accel);
-}
 pub fn angle_at_distance(distance: f64, target_width: f64) -> f64 {
     target_width / distance
 }
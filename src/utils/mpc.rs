@@ -0,0 +1,226 @@
+use oort_api::prelude::*;
+
+use crate::utils::{
+    attitude_acceleration, class_info, thrust, trajectory_acceleration, FriendlyHulls, HeadingGoal,
+    ThrustLimits, TrajectoryGoal, VecUtils,
+};
+
+const HORIZON_TICKS: usize = 20;
+const THRUST_DIRECTIONS: usize = 8;
+const HEADING_OFFSETS: [f64; 3] = [0.0, -0.05, 0.05];
+const DODGE_SPEED: f64 = 100.0;
+/// How much better a new plan has to score before it replaces last tick's.
+const SWITCH_MARGIN: f64 = 1e-3;
+
+/// Relative importance of each term in the controller's objective.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MpcWeights {
+    pub aim: f64,
+    pub thrust: f64,
+    pub dodge: f64,
+}
+
+impl MpcWeights {
+    pub fn fighter() -> MpcWeights {
+        MpcWeights {
+            aim: 100.0,
+            thrust: 1.0,
+            dodge: 0.5,
+        }
+    }
+}
+
+/// What the controller works toward this tick. `aim` is where the gun should point,
+/// relative to us, and is assumed to move with the target. Thrust follows `evasion` when
+/// there is a dodge to make, otherwise the trajectory onto `station` if there is one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MpcGoal {
+    pub aim: Vec2,
+    pub target_position: Vec2,
+    pub target_velocity: Vec2,
+    pub station: Option<TrajectoryGoal>,
+    pub evasion: Option<Vec2>,
+}
+
+/// A body-frame thrust held over the horizon, while the attitude controller tracks the aim
+/// direction turned by `heading_offset`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MpcControl {
+    pub thrust: Vec2,
+    pub heading_offset: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RigidBody {
+    position: Vec2,
+    velocity: Vec2,
+    heading: f64,
+    angular_velocity: f64,
+}
+
+/// Short-horizon model-predictive controller for turning and thrusting together. Each tick
+/// it rolls a set of candidate controls forward on the ship's rigid-body model, scores them
+/// on aim error, station keeping and dodging, and applies the first step of the best one.
+/// It is the only thing that thrusts while it is in charge.
+pub struct Mpc {
+    class: Class,
+    weights: MpcWeights,
+    last: Option<MpcControl>,
+}
+
+impl Mpc {
+    pub fn new(class: Class, weights: MpcWeights) -> Mpc {
+        Mpc {
+            class,
+            weights,
+            last: None,
+        }
+    }
+
+    pub fn plan(&mut self, goal: &MpcGoal) -> MpcControl {
+        let limits =
+            ThrustLimits::for_class(self.class, active_abilities().get_ability(Ability::Boost));
+        let max_angular_acceleration = class_info(self.class).angular_acceleration;
+        let start = RigidBody {
+            position: position(),
+            velocity: velocity(),
+            heading: heading(),
+            angular_velocity: angular_velocity(),
+        };
+        let cost = |control: &MpcControl| {
+            self.rollout(goal, start, control, &limits, max_angular_acceleration)
+        };
+        let mut best = self.last.map_or(
+            MpcControl {
+                thrust: vec2(0.0, 0.0),
+                heading_offset: 0.0,
+            },
+            |c| MpcControl {
+                thrust: limits.fit(c.thrust, 0.0),
+                ..c
+            },
+        );
+        let mut best_cost = cost(&best) - SWITCH_MARGIN;
+        for control in candidates(&limits) {
+            let c = cost(&control);
+            if c < best_cost {
+                best = control;
+                best_cost = c;
+            }
+        }
+        self.last = Some(best);
+        best
+    }
+
+    /// Plans and applies the first tick of the best control, keeping clear of `hulls`.
+    pub fn step(&mut self, goal: &MpcGoal, hulls: &FriendlyHulls) {
+        let control = self.plan(goal);
+        let heading_goal = aim_goal(goal, velocity(), control.heading_offset);
+        torque(attitude_acceleration(
+            &heading_goal,
+            heading(),
+            angular_velocity(),
+            class_info(self.class).angular_acceleration,
+        ));
+        thrust(hulls.avoid(control.thrust.rotate(heading())));
+    }
+
+    fn rollout(
+        &self,
+        goal: &MpcGoal,
+        start: RigidBody,
+        control: &MpcControl,
+        limits: &ThrustLimits,
+        max_angular_acceleration: f64,
+    ) -> f64 {
+        let aim_point = start.position + goal.aim;
+        let mut body = start;
+        let mut cost = 0.0;
+        for tick in 1..=HORIZON_TICKS {
+            let heading_goal = aim_goal(
+                &MpcGoal {
+                    aim: aim_point + goal.target_velocity * ((tick - 1) as f64 * TICK_LENGTH)
+                        - body.position,
+                    ..*goal
+                },
+                body.velocity,
+                control.heading_offset,
+            );
+            body.angular_velocity += attitude_acceleration(
+                &heading_goal,
+                body.heading,
+                body.angular_velocity,
+                max_angular_acceleration,
+            ) * TICK_LENGTH;
+            body.heading += body.angular_velocity * TICK_LENGTH;
+            let thrust = control.thrust.rotate(body.heading);
+            let t = (tick - 1) as f64 * TICK_LENGTH;
+            let reference = goal.evasion.or_else(|| {
+                goal.station.map(|station| {
+                    let station = TrajectoryGoal {
+                        position: station.position + station.velocity * t,
+                        ..station
+                    };
+                    limits.fit(
+                        trajectory_acceleration(
+                            &station,
+                            body.position,
+                            body.velocity,
+                            body.heading,
+                            None,
+                            limits,
+                        ),
+                        body.heading,
+                    )
+                })
+            });
+            let thrust_error =
+                reference.map_or(0.0, |r| (thrust - r).length() / limits.forward.max(1e-9));
+            body.velocity += thrust * TICK_LENGTH;
+            body.position += body.velocity * TICK_LENGTH;
+
+            let t = tick as f64 * TICK_LENGTH;
+            let aim = aim_point + goal.target_velocity * t - body.position;
+            let aim_error = angle_diff(body.heading, aim.angle());
+            let los = goal.target_position + goal.target_velocity * t - body.position;
+            let crossing_speed = (body.velocity - goal.target_velocity)
+                .wedge(los.normalize())
+                .abs();
+            cost += self.weights.aim * aim_error * aim_error
+                + self.weights.thrust * thrust_error * thrust_error
+                - self.weights.dodge * (crossing_speed / DODGE_SPEED).min(1.0);
+        }
+        cost / HORIZON_TICKS as f64
+    }
+}
+
+/// Heading goal that tracks the aim direction, including how fast it sweeps across the
+/// sky as we and the target move.
+fn aim_goal(goal: &MpcGoal, velocity: Vec2, offset: f64) -> HeadingGoal {
+    let dv = goal.target_velocity - velocity;
+    let rate = goal.aim.wedge(dv) / goal.aim.square_magnitude().max(1.0);
+    HeadingGoal::moving(goal.aim.angle() + offset, rate, 0.0)
+}
+
+/// Full thrust in evenly spaced body-frame directions, plus coasting, each paired with
+/// every heading offset.
+fn candidates(limits: &ThrustLimits) -> Vec<MpcControl> {
+    let mut thrusts = (0..THRUST_DIRECTIONS)
+        .map(|i| {
+            let direction = Vec2::angle_length(TAU * i as f64 / THRUST_DIRECTIONS as f64, 1.0);
+            direction * limits.along(direction, 0.0)
+        })
+        .collect::<Vec<_>>();
+    thrusts.push(vec2(0.0, 0.0));
+    thrusts
+        .iter()
+        .flat_map(|&thrust| {
+            HEADING_OFFSETS
+                .iter()
+                .map(move |&heading_offset| MpcControl {
+                    thrust,
+                    heading_offset,
+                })
+        })
+        .collect()
+}
@@ -0,0 +1,95 @@
+use oort_api::prelude::*;
+
+use crate::utils::{class_guns, class_info, hull_size};
+
+const CANDIDATE_HEADINGS: usize = 72;
+/// Slack on a gun's arc, so a fixed gun counts as bearing once it is nearly on target.
+const BEAR_TOLERANCE: f64 = 0.05;
+
+/// How much each concern counts when picking a hull heading. Weapons and exposure are
+/// fractions of their best case, main-gun error is in half-turns and turning in seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrientationWeights {
+    pub weapons: f64,
+    pub exposure: f64,
+    pub main_gun: f64,
+    pub turn: f64,
+}
+
+impl OrientationWeights {
+    pub fn frigate() -> OrientationWeights {
+        OrientationWeights {
+            weapons: 1.0,
+            exposure: 0.5,
+            main_gun: 2.0,
+            turn: 0.1,
+        }
+    }
+
+    /// The cruiser's one gun is a turret that bears whatever the heading.
+    pub fn cruiser() -> OrientationWeights {
+        OrientationWeights {
+            weapons: 0.0,
+            exposure: 1.0,
+            main_gun: 0.0,
+            turn: 0.05,
+        }
+    }
+}
+
+/// What the hull should be oriented for: which gun is assigned to which target, the
+/// bearing of the main threat, and the heading a fixed main gun needs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrientationGoal {
+    pub assignments: Vec<(usize, Vec2)>,
+    pub threat_axis: Option<f64>,
+    pub main_gun: Option<f64>,
+}
+
+/// Picks the heading that lets the most assigned guns bear while showing the threat the
+/// narrowest hull, traded against lining up the main gun and the time it takes to turn.
+/// Turrets that traverse all the way round bear whatever the heading and are left out.
+pub fn plan_heading(class: Class, goal: &OrientationGoal, weights: &OrientationWeights) -> f64 {
+    let guns = class_guns(class);
+    let limited = goal
+        .assignments
+        .iter()
+        .filter(|&&(gun, _)| guns.get(gun).is_some_and(|g| g.arc_half_width < PI))
+        .collect::<Vec<_>>();
+    let (length, width) = hull_size(class).unwrap_or((1.0, 1.0));
+    let widest = length.hypot(width);
+    let angular_acceleration = class_info(class).angular_acceleration.max(1e-3);
+    let score = |candidate: f64| {
+        let bearing = limited
+            .iter()
+            .filter(|&&&(gun, target)| {
+                let g = guns[gun];
+                let bearing = (target - position()).angle();
+                angle_diff(candidate + g.arc_center, bearing).abs()
+                    <= g.arc_half_width + BEAR_TOLERANCE
+            })
+            .count() as f64
+            / limited.len().max(1) as f64;
+        let exposure = goal.threat_axis.map_or(0.0, |axis| {
+            let off_axis = angle_diff(candidate, axis);
+            (length * off_axis.sin().abs() + width * off_axis.cos().abs()) / widest
+        });
+        let main_gun = goal
+            .main_gun
+            .map_or(0.0, |h| angle_diff(candidate, h).abs() / PI);
+        // Rest-to-rest bang-bang time; cheap enough to run on every candidate.
+        let turn_time =
+            2.0 * (angle_diff(heading(), candidate).abs() / angular_acceleration).sqrt();
+        weights.weapons * bearing
+            - weights.exposure * exposure
+            - weights.main_gun * main_gun
+            - weights.turn * turn_time
+    };
+    (0..CANDIDATE_HEADINGS)
+        .map(|i| TAU * i as f64 / CANDIDATE_HEADINGS as f64)
+        .chain(goal.main_gun)
+        .chain([heading()])
+        .map(|candidate| (candidate, score(candidate)))
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .map_or(heading(), |(candidate, _)| candidate)
+}
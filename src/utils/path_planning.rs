@@ -0,0 +1,148 @@
+use oort_api::prelude::*;
+
+use crate::utils::hull_size;
+
+pub const BEACON_CHANNEL: usize = 9;
+const CLEARANCE: f64 = 40.0;
+
+/// A friendly hull as reported by `send_class_and_position`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hull {
+    pub class: Class,
+    pub position: Vec2,
+    pub heading: f64,
+}
+
+impl Hull {
+    fn half_extents(&self, clearance: f64) -> Vec2 {
+        let (width, height) = hull_size(self.class).unwrap_or((0.0, 0.0));
+        vec2(width + clearance, height + clearance)
+    }
+
+    fn local(&self, point: Vec2) -> Vec2 {
+        (point - self.position).rotate(-self.heading)
+    }
+
+    fn contains(&self, point: Vec2, clearance: f64) -> bool {
+        let local = self.local(point);
+        let half = self.half_extents(clearance);
+        local.x.abs() < half.x && local.y.abs() < half.y
+    }
+
+    /// Whether the segment `from`-`to` passes through the hull grown by `clearance`.
+    fn blocks(&self, from: Vec2, to: Vec2, clearance: f64) -> bool {
+        let a = self.local(from);
+        let d = self.local(to) - a;
+        let half = self.half_extents(clearance);
+        let mut t0: f64 = 0.0;
+        let mut t1: f64 = 1.0;
+        for (p, dp, h) in [(a.x, d.x, half.x), (a.y, d.y, half.y)] {
+            if dp.abs() < 1e-9 {
+                if p.abs() > h {
+                    return false;
+                }
+                continue;
+            }
+            let ta = (-h - p) / dp;
+            let tb = (h - p) / dp;
+            t0 = t0.max(ta.min(tb));
+            t1 = t1.min(ta.max(tb));
+            if t0 > t1 {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn corners(&self) -> [Vec2; 4] {
+        let half = self.half_extents(CLEARANCE * 1.5);
+        [
+            vec2(half.x, half.y),
+            vec2(-half.x, half.y),
+            vec2(-half.x, -half.y),
+            vec2(half.x, -half.y),
+        ]
+        .map(|c| c.rotate(self.heading) + self.position)
+    }
+}
+
+/// Returns the next point to fly to on the shortest path from `from` to `goal` through
+/// the corners of the given hulls, or `goal` itself if the straight line is clear.
+pub fn plan_path(from: Vec2, goal: Vec2, hulls: &[Hull]) -> Vec2 {
+    // A missile that spawns inside its launcher's clearance only has to avoid the hull.
+    let first_hop_clear = |to: Vec2| {
+        hulls.iter().all(|h| {
+            let clearance = if h.contains(from, CLEARANCE) {
+                0.0
+            } else {
+                CLEARANCE
+            };
+            !h.blocks(from, to, clearance)
+        })
+    };
+    let clear = |a: Vec2, b: Vec2| hulls.iter().all(|h| !h.blocks(a, b, CLEARANCE));
+    if first_hop_clear(goal) {
+        return goal;
+    }
+    let corners = hulls.iter().flat_map(|h| h.corners()).collect::<Vec<_>>();
+    corners
+        .iter()
+        .filter(|&&c| first_hop_clear(c))
+        .filter_map(|&c| {
+            let rest = if clear(c, goal) {
+                Some(c.distance(goal))
+            } else {
+                corners
+                    .iter()
+                    .filter(|&&d| d != c && clear(c, d) && clear(d, goal))
+                    .map(|&d| c.distance(d) + d.distance(goal))
+                    .min_by(|a, b| a.partial_cmp(b).unwrap())
+            };
+            rest.map(|r| (c, from.distance(c) + r))
+        })
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .map_or(goal, |(c, _)| c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frigate() -> Hull {
+        Hull {
+            class: Class::Frigate,
+            position: vec2(0.0, 0.0),
+            heading: 0.0,
+        }
+    }
+
+    #[test]
+    fn blocks_only_segments_through_the_grown_hull() {
+        let hull = frigate();
+        assert!(hull.blocks(vec2(-500.0, 0.0), vec2(500.0, 0.0), CLEARANCE));
+        assert!(hull.blocks(vec2(-500.0, 70.0), vec2(500.0, 70.0), CLEARANCE));
+        assert!(!hull.blocks(vec2(-500.0, 70.0), vec2(500.0, 70.0), 0.0));
+        assert!(!hull.blocks(vec2(-500.0, 300.0), vec2(500.0, 300.0), CLEARANCE));
+        assert!(!hull.blocks(vec2(-500.0, 0.0), vec2(-300.0, 0.0), CLEARANCE));
+    }
+
+    #[test]
+    fn clear_line_goes_straight_to_the_goal() {
+        let goal = vec2(500.0, 300.0);
+        assert_eq!(plan_path(vec2(-500.0, 300.0), goal, &[frigate()]), goal);
+    }
+
+    #[test]
+    fn blocked_line_goes_via_a_corner() {
+        let hull = frigate();
+        let next = plan_path(vec2(-500.0, 0.0), vec2(500.0, 0.0), &[hull]);
+        assert!(hull.corners().contains(&next), "{next:?}");
+    }
+
+    #[test]
+    fn launch_from_inside_the_clearance_may_leave_directly() {
+        let goal = vec2(500.0, 70.0);
+        assert!(frigate().contains(vec2(0.0, 70.0), CLEARANCE));
+        assert_eq!(plan_path(vec2(0.0, 70.0), goal, &[frigate()]), goal);
+    }
+}
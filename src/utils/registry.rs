@@ -0,0 +1,77 @@
+use oort_api::prelude::*;
+
+/// Performance figures of a ship class, as the simulator defines them. Boost is the extra
+/// forward acceleration from `Ability::Boost`, zero for classes without it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClassInfo {
+    pub forward_acceleration: f64,
+    pub backward_acceleration: f64,
+    pub lateral_acceleration: f64,
+    pub angular_acceleration: f64,
+    pub boost_acceleration: f64,
+    /// Delta-v in the tank. Only ordnance runs out.
+    pub fuel: f64,
+}
+
+pub fn class_info(class: Class) -> ClassInfo {
+    let (forward, backward, lateral, angular, boost, fuel) = match class {
+        Class::Fighter => (60.0, 30.0, 30.0, TAU, 100.0, f64::INFINITY),
+        Class::Frigate => (10.0, 5.0, 5.0, PI / 4.0, 0.0, f64::INFINITY),
+        Class::Cruiser => (5.0, 2.5, 2.5, PI / 8.0, 0.0, f64::INFINITY),
+        Class::Missile => (300.0, 0.0, 100.0, 4.0 * PI, 100.0, 2000.0),
+        Class::Torpedo => (70.0, 0.0, 20.0, TAU, 0.0, 1000.0),
+        _ => (0.0, 0.0, 0.0, 0.0, 0.0, 0.0),
+    };
+    ClassInfo {
+        forward_acceleration: forward,
+        backward_acceleration: backward,
+        lateral_acceleration: lateral,
+        angular_acceleration: angular,
+        boost_acceleration: boost,
+        fuel,
+    }
+}
+
+/// A ship's gun: muzzle speed, ticks between shots, how long its bullets live and the arc
+/// it can bear on, as a centre and half-width relative to the ship's heading.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GunInfo {
+    pub bullet_speed: f64,
+    pub reload_ticks: u32,
+    pub bullet_lifetime: f64,
+    pub arc_center: f64,
+    pub arc_half_width: f64,
+}
+
+impl GunInfo {
+    /// Furthest a bullet can travel from a stationary gun.
+    pub fn range(&self) -> f64 {
+        self.bullet_speed * self.bullet_lifetime
+    }
+}
+
+/// Guns of `class`, in weapon-index order. Missile and torpedo launchers aren't included.
+pub fn class_guns(class: Class) -> Vec<GunInfo> {
+    let fixed = |bullet_speed: f64, reload_ticks: u32, bullet_lifetime: f64| GunInfo {
+        bullet_speed,
+        reload_ticks,
+        bullet_lifetime,
+        arc_center: 0.0,
+        arc_half_width: 0.0,
+    };
+    let turret = |arc_center: f64, arc_half_width: f64, gun: GunInfo| GunInfo {
+        arc_center,
+        arc_half_width,
+        ..gun
+    };
+    match class {
+        Class::Fighter => vec![fixed(1000.0, 4, 3.0)],
+        Class::Frigate => vec![
+            fixed(4000.0, 120, 2.0),
+            turret(0.0, PI, fixed(1000.0, 4, 1.5)),
+            turret(0.0, PI, fixed(1000.0, 4, 1.5)),
+        ],
+        Class::Cruiser => vec![turret(0.0, PI, fixed(2000.0, 24, 2.0))],
+        _ => Vec::new(),
+    }
+}
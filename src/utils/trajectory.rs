@@ -0,0 +1,361 @@
+use oort_api::prelude::*;
+
+use crate::utils::{
+    boost_max_acceleration, class_info, stopping_rate, thrust, ticks_to_align, turn_to,
+    FriendlyHulls, HeadingGoal,
+};
+
+/// Fraction of forward acceleration below which the planner leaves the heading alone.
+const HOLD_HEADING_FRACTION: f64 = 0.1;
+/// Ticks a flip-time estimate is reused for before the turn is simulated again.
+const FLIP_ESTIMATE_TICKS: u32 = 10;
+/// Swing of the braking heading that makes the flip-time estimate stale early.
+const FLIP_ESTIMATE_ANGLE: f64 = 0.05;
+
+/// How a move should end.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arrival {
+    /// Come to rest on the point.
+    Stop,
+    /// Reach the point as soon as possible, with whatever velocity that leaves.
+    FlyThrough,
+    /// Reach the point, which moves with the goal velocity, travelling alongside it.
+    MatchVelocity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrajectoryGoal {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub arrival: Arrival,
+}
+
+impl TrajectoryGoal {
+    pub fn stop_at(position: Vec2) -> TrajectoryGoal {
+        TrajectoryGoal {
+            position,
+            velocity: vec2(0.0, 0.0),
+            arrival: Arrival::Stop,
+        }
+    }
+
+    pub fn fly_through(position: Vec2) -> TrajectoryGoal {
+        TrajectoryGoal {
+            position,
+            velocity: vec2(0.0, 0.0),
+            arrival: Arrival::FlyThrough,
+        }
+    }
+
+    pub fn rendezvous(position: Vec2, velocity: Vec2) -> TrajectoryGoal {
+        TrajectoryGoal {
+            position,
+            velocity,
+            arrival: Arrival::MatchVelocity,
+        }
+    }
+}
+
+/// Body-frame acceleration limits of a ship.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThrustLimits {
+    pub forward: f64,
+    pub backward: f64,
+    pub lateral: f64,
+}
+
+impl ThrustLimits {
+    pub fn current() -> ThrustLimits {
+        let ma = boost_max_acceleration();
+        ThrustLimits {
+            forward: ma.x,
+            backward: max_backward_acceleration(),
+            lateral: ma.y,
+        }
+    }
+
+    pub fn for_class(class: Class, boosted: bool) -> ThrustLimits {
+        let info = class_info(class);
+        let boost = if boosted {
+            info.boost_acceleration
+        } else {
+            0.0
+        };
+        ThrustLimits {
+            forward: info.forward_acceleration + boost,
+            backward: info.backward_acceleration,
+            lateral: info.lateral_acceleration,
+        }
+    }
+
+    /// Largest acceleration available along the world-frame `direction` at `heading`.
+    pub fn along(&self, direction: Vec2, heading: f64) -> f64 {
+        if direction.length() < 1e-9 {
+            return 0.0;
+        }
+        let body = direction.normalize().rotate(-heading);
+        let x_limit = if body.x > 1e-9 {
+            self.forward / body.x
+        } else if body.x < -1e-9 {
+            self.backward / -body.x
+        } else {
+            f64::INFINITY
+        };
+        let y_limit = if body.y.abs() > 1e-9 {
+            self.lateral / body.y.abs()
+        } else {
+            f64::INFINITY
+        };
+        x_limit.min(y_limit)
+    }
+
+    /// How far `base`, which the ship can already produce at `heading`, can be extended
+    /// along the world-frame `direction` before it runs into a thrust limit.
+    pub fn room(&self, base: Vec2, direction: Vec2, heading: f64) -> f64 {
+        let base = base.rotate(-heading);
+        let direction = direction.rotate(-heading);
+        let axis = |value: f64, step: f64, low: f64, high: f64| {
+            if step > 1e-9 {
+                (high - value) / step
+            } else if step < -1e-9 {
+                (value - low) / -step
+            } else {
+                f64::INFINITY
+            }
+        };
+        axis(base.x, direction.x, -self.backward, self.forward)
+            .min(axis(base.y, direction.y, -self.lateral, self.lateral))
+            .max(0.0)
+    }
+
+    /// Scales `a` down, keeping its direction, until the ship can produce it at `heading`.
+    pub fn fit(&self, a: Vec2, heading: f64) -> Vec2 {
+        if a.length() < 1e-9 {
+            return a;
+        }
+        a * (self.along(a, heading) / a.length()).min(1.0)
+    }
+}
+
+/// World-frame acceleration that takes a ship with the given state onto `goal` in minimum
+/// time.
+///
+/// Works along the line to the goal and across it, relative to the goal's velocity. Along
+/// it the ship closes at the fastest speed it can still shed before arriving, either by
+/// braking at its current heading or, if `flip_time` is given, by turning round in that
+/// time and braking on the main engine. The drift across the line is cancelled with
+/// whatever acceleration the along-track demand leaves over at the current heading.
+pub fn trajectory_acceleration(
+    goal: &TrajectoryGoal,
+    position: Vec2,
+    velocity: Vec2,
+    heading: f64,
+    flip_time: Option<f64>,
+    limits: &ThrustLimits,
+) -> Vec2 {
+    let dp = goal.position - position;
+    let dv = velocity - goal.velocity;
+    let distance = dp.length();
+    if distance < 1e-9 {
+        return -dv / TICK_LENGTH;
+    }
+    let along = dp / distance;
+    let across = vec2(-along.y, along.x);
+    let closing = dv.dot(along);
+    let drift = dv.dot(across);
+    let along_acceleration = match goal.arrival {
+        Arrival::FlyThrough => limits.forward,
+        Arrival::Stop | Arrival::MatchVelocity => {
+            let hold = stopping_speed(distance, limits.along(-along, heading));
+            let flip = flip_time.map_or(0.0, |t| {
+                let a = limits.forward;
+                a * ((t * t + 2.0 * distance / a).sqrt() - t)
+            });
+            (hold.max(flip) - closing) / TICK_LENGTH
+        }
+    };
+    let along_acceleration =
+        along_acceleration.clamp(-limits.along(-along, heading), limits.along(along, heading));
+    let base = along * along_acceleration;
+    let correction = across * -drift.signum();
+    let room = limits.room(base, correction, heading);
+    base + correction * (drift.abs() / TICK_LENGTH).min(room)
+}
+
+/// Largest speed from which `distance` can be covered exactly while braking at
+/// `acceleration`, on the simulator's discrete ticks.
+pub fn stopping_speed(distance: f64, acceleration: f64) -> f64 {
+    if acceleration <= 0.0 {
+        return 0.0;
+    }
+    let dt = TICK_LENGTH;
+    stopping_rate(distance, acceleration * dt * dt) / dt
+}
+
+/// Flies minimum-time trajectories, carrying the estimate of how long the ship takes to
+/// turn round and brake from tick to tick rather than simulating the turn every tick.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Autopilot {
+    /// Tick the flip time was estimated, the braking heading it was for and the ticks.
+    flip_estimate: Option<(u32, f64, u32)>,
+}
+impl Default for Autopilot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Autopilot {
+    pub fn new() -> Autopilot {
+        Autopilot {
+            flip_estimate: None,
+        }
+    }
+
+    /// Turns and thrusts along the minimum-time trajectory to `goal`, pointing the main
+    /// engine wherever the trajectory needs the most acceleration and keeping clear of
+    /// `hulls`.
+    pub fn fly(&mut self, goal: &TrajectoryGoal, hulls: &FriendlyHulls) {
+        let limits = ThrustLimits::current();
+        let braking_heading = (position() - goal.position).angle();
+        let flip_time = self.flip_ticks(braking_heading) as f64 * TICK_LENGTH;
+        let a = trajectory_acceleration(
+            goal,
+            position(),
+            velocity(),
+            heading(),
+            Some(flip_time),
+            &limits,
+        );
+        if a.length() > limits.forward * HOLD_HEADING_FRACTION {
+            turn_to(a.angle());
+        }
+        thrust(hulls.avoid(limits.fit(a, heading())));
+    }
+
+    /// Ticks to turn onto `braking_heading`, reusing the last estimate while it is recent
+    /// and for much the same heading.
+    fn flip_ticks(&mut self, braking_heading: f64) -> u32 {
+        let fresh = self.flip_estimate.filter(|&(tick, estimated_heading, _)| {
+            current_tick() - tick < FLIP_ESTIMATE_TICKS
+                && angle_diff(estimated_heading, braking_heading).abs() < FLIP_ESTIMATE_ANGLE
+        });
+        if let Some((_, _, ticks)) = fresh {
+            return ticks;
+        }
+        let ticks = ticks_to_align(
+            &HeadingGoal::fixed(braking_heading),
+            heading(),
+            angular_velocity(),
+            max_angular_acceleration(),
+        );
+        self.flip_estimate = Some((current_tick(), braking_heading, ticks));
+        ticks
+    }
+}
+
+/// Thrusts along the trajectory to `goal` without turning, for ships whose heading is
+/// spoken for (aiming guns, holding a broadside), keeping clear of `hulls`.
+pub fn thrust_toward(goal: &TrajectoryGoal, hulls: &FriendlyHulls) {
+    let limits = ThrustLimits::current();
+    let a = trajectory_acceleration(goal, position(), velocity(), heading(), None, &limits);
+    thrust(hulls.avoid(limits.fit(a, heading())));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_TICKS: u32 = 3600;
+
+    /// Point mass holding `heading`, stepped on the simulator's model.
+    struct Plant {
+        position: Vec2,
+        velocity: Vec2,
+        heading: f64,
+        limits: ThrustLimits,
+    }
+
+    impl Plant {
+        fn fighter(velocity: Vec2) -> Plant {
+            Plant {
+                position: vec2(0.0, 0.0),
+                velocity,
+                heading: 0.0,
+                limits: ThrustLimits::for_class(Class::Fighter, false),
+            }
+        }
+
+        fn step(&mut self, goal: &TrajectoryGoal) -> Vec2 {
+            let a = trajectory_acceleration(
+                goal,
+                self.position,
+                self.velocity,
+                self.heading,
+                None,
+                &self.limits,
+            );
+            let a = self.limits.fit(a, self.heading);
+            self.velocity += a * TICK_LENGTH;
+            self.position += self.velocity * TICK_LENGTH;
+            a
+        }
+
+        /// Ticks until the plant sits on `goal` at the goal's speed.
+        fn arrive(&mut self, goal: impl Fn(u32) -> TrajectoryGoal) -> u32 {
+            for tick in 0..MAX_TICKS {
+                let goal = goal(tick);
+                if self.position.distance(goal.position) < 1.0
+                    && (self.velocity - goal.velocity).length() < 1.0
+                {
+                    return tick;
+                }
+                self.step(&goal);
+            }
+            MAX_TICKS
+        }
+    }
+
+    #[test]
+    fn stops_on_the_goal_without_overshoot() {
+        let goal = TrajectoryGoal::stop_at(vec2(2000.0, 0.0));
+        let mut plant = Plant::fighter(vec2(0.0, 0.0));
+        let mut furthest: f64 = 0.0;
+        for _ in 0..MAX_TICKS {
+            plant.step(&goal);
+            furthest = furthest.max(plant.position.x);
+        }
+        assert!(plant.position.distance(goal.position) < 1.0);
+        assert!(plant.velocity.length() < 1.0);
+        assert!(furthest < goal.position.x + 1.0, "overshot to {furthest}");
+    }
+
+    #[test]
+    fn cancels_drift_without_starving_the_approach() {
+        let goal = TrajectoryGoal::stop_at(vec2(2000.0, 0.0));
+        let mut drifting = Plant::fighter(vec2(0.0, 100.0));
+        let mut straight = Plant::fighter(vec2(0.0, 0.0));
+        let a = drifting.step(&goal);
+        let b = straight.step(&goal);
+        assert!(
+            (a.x - b.x).abs() < 1e-6,
+            "approach cut from {} to {}",
+            b.x,
+            a.x
+        );
+        assert!(a.y < 0.0, "drift left alone");
+        let ticks = drifting.arrive(|_| goal);
+        assert!(ticks < MAX_TICKS, "never arrived");
+    }
+
+    #[test]
+    fn rendezvous_matches_a_moving_goal() {
+        let velocity = vec2(-50.0, 80.0);
+        let goal = |tick: u32| {
+            let t = tick as f64 * TICK_LENGTH;
+            TrajectoryGoal::rendezvous(vec2(-1000.0, -1000.0) + velocity * t, velocity)
+        };
+        let ticks = Plant::fighter(vec2(0.0, 0.0)).arrive(goal);
+        assert!(ticks < MAX_TICKS, "never caught the goal");
+    }
+}
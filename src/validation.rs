@@ -0,0 +1,218 @@
+use crate::utils::class_max_acceleration;
+use oort_api::prelude::*;
+
+/// Consistent updates a new or suspect contact needs before weapons are assigned to it.
+const CONFIRM_UPDATES: u32 = 5;
+/// Slack on the class's acceleration limit for radar noise.
+const ACCELERATION_TOLERANCE: f64 = 2.0;
+/// Position and velocity error from radar noise alone, on top of what the class could do.
+const POSITION_NOISE: f64 = 50.0;
+const VELOCITY_NOISE: f64 = 50.0;
+/// Returns weaker than this are too easily jammed or spoofed to count as confirmation.
+const MIN_CONFIRMING_SNR: f64 = 10.0;
+
+/// Why a contact's latest return doesn't add up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Suspicion {
+    /// It moved further than its last velocity and class acceleration allow.
+    Teleport,
+    /// Its velocity changed faster than its class can accelerate.
+    ImpossibleAcceleration,
+    /// It came back as a different class.
+    ClassChange,
+    /// The return was too weak to trust.
+    WeakReturn,
+}
+
+/// Checks a new return of a contact against the last one, `dt` seconds ago: `None` if it
+/// is physically consistent with a `class` ship, otherwise what gives it away as a decoy
+/// or a jammed return.
+pub fn plausibility(
+    class: Class,
+    last_position: Vec2,
+    last_velocity: Vec2,
+    dt: f64,
+    contact: &ScanResult,
+) -> Option<Suspicion> {
+    if contact.class != class {
+        return Some(Suspicion::ClassChange);
+    }
+    if let Some(suspicion) = kinematics(
+        class,
+        last_position,
+        last_velocity,
+        dt,
+        contact.position,
+        contact.velocity,
+    ) {
+        return Some(suspicion);
+    }
+    if contact.snr < MIN_CONFIRMING_SNR {
+        return Some(Suspicion::WeakReturn);
+    }
+    None
+}
+
+/// Checks that a `class` ship last seen at `last_position` and `last_velocity` could be at
+/// `position` with `velocity` `dt` seconds later. For reports with no radar return behind
+/// them, such as another ship's track.
+pub fn kinematics(
+    class: Class,
+    last_position: Vec2,
+    last_velocity: Vec2,
+    dt: f64,
+    position: Vec2,
+    velocity: Vec2,
+) -> Option<Suspicion> {
+    let max_acceleration = class_max_acceleration(class) * ACCELERATION_TOLERANCE;
+    if (velocity - last_velocity).length() > max_acceleration * dt + VELOCITY_NOISE {
+        return Some(Suspicion::ImpossibleAcceleration);
+    }
+    let expected = last_position + last_velocity * dt;
+    if position.distance(expected) > max_acceleration * dt * dt / 2.0 + POSITION_NOISE {
+        return Some(Suspicion::Teleport);
+    }
+    None
+}
+
+/// Whether a track can be trusted. Every track starts in quarantine and is released after
+/// `CONFIRM_UPDATES` consistent returns in a row; any suspicious return puts it back.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Validation {
+    consistent: u32,
+    pub suspicion: Option<Suspicion>,
+}
+
+impl Default for Validation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Validation {
+    pub fn new() -> Validation {
+        Validation {
+            consistent: 0,
+            suspicion: None,
+        }
+    }
+
+    pub fn record(&mut self, suspicion: Option<Suspicion>) {
+        match suspicion {
+            Some(s) => {
+                debug!("suspect contact: {:?}", s);
+                self.consistent = 0;
+                self.suspicion = Some(s);
+            }
+            None => {
+                self.consistent += 1;
+                if self.consistent >= CONFIRM_UPDATES {
+                    self.suspicion = None;
+                }
+            }
+        }
+    }
+
+    pub fn is_confirmed(&self) -> bool {
+        self.consistent >= CONFIRM_UPDATES
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A radar revisit a few targets into the cruiser's rotation.
+    const DT: f64 = 6.0 * TICK_LENGTH;
+
+    fn contact(class: Class, position: Vec2, velocity: Vec2) -> ScanResult {
+        ScanResult {
+            class,
+            position,
+            velocity,
+            snr: 20.0,
+            rssi: 0.0,
+        }
+    }
+
+    #[test]
+    fn fast_track_on_its_course_is_plausible() {
+        let (position, velocity) = (vec2(5000.0, 0.0), vec2(-2000.0, 500.0));
+        let seen = contact(Class::Missile, position + velocity * DT, velocity);
+        assert_eq!(
+            plausibility(Class::Missile, position, velocity, DT, &seen),
+            None
+        );
+    }
+
+    #[test]
+    fn fast_track_at_full_acceleration_is_plausible() {
+        let (position, velocity) = (vec2(5000.0, 0.0), vec2(-2000.0, 500.0));
+        let a = vec2(0.0, class_max_acceleration(Class::Missile));
+        let seen = contact(
+            Class::Missile,
+            position + velocity * DT + a * DT * DT / 2.0,
+            velocity + a * DT,
+        );
+        assert_eq!(
+            plausibility(Class::Missile, position, velocity, DT, &seen),
+            None
+        );
+    }
+
+    #[test]
+    fn fast_track_extrapolated_twice_would_teleport() {
+        // What comparing against a position already advanced to this tick amounts to.
+        let (position, velocity) = (vec2(5000.0, 0.0), vec2(-2000.0, 500.0));
+        let seen = contact(Class::Missile, position + velocity * DT, velocity);
+        assert_eq!(
+            plausibility(
+                Class::Missile,
+                position + velocity * DT,
+                velocity,
+                DT,
+                &seen
+            ),
+            Some(Suspicion::Teleport)
+        );
+    }
+
+    #[test]
+    fn jump_off_course_is_a_teleport() {
+        let (position, velocity) = (vec2(0.0, 0.0), vec2(100.0, 0.0));
+        let seen = contact(
+            Class::Fighter,
+            position + velocity * DT + vec2(0.0, 500.0),
+            velocity,
+        );
+        assert_eq!(
+            plausibility(Class::Fighter, position, velocity, DT, &seen),
+            Some(Suspicion::Teleport)
+        );
+    }
+
+    #[test]
+    fn sudden_velocity_change_is_impossible() {
+        let (position, velocity) = (vec2(0.0, 0.0), vec2(100.0, 0.0));
+        let seen = contact(Class::Cruiser, position + velocity * DT, vec2(-400.0, 0.0));
+        assert_eq!(
+            plausibility(Class::Cruiser, position, velocity, DT, &seen),
+            Some(Suspicion::ImpossibleAcceleration)
+        );
+    }
+
+    #[test]
+    fn class_change_and_weak_return_are_suspect() {
+        let (position, velocity) = (vec2(0.0, 0.0), vec2(100.0, 0.0));
+        let seen = contact(Class::Frigate, position + velocity * DT, velocity);
+        assert_eq!(
+            plausibility(Class::Fighter, position, velocity, DT, &seen),
+            Some(Suspicion::ClassChange)
+        );
+        let weak = ScanResult { snr: 1.0, ..seen };
+        assert_eq!(
+            plausibility(Class::Frigate, position, velocity, DT, &weak),
+            Some(Suspicion::WeakReturn)
+        );
+    }
+}